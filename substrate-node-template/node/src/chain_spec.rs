@@ -6,7 +6,10 @@ use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{sr25519, Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	Permill,
+};
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -155,9 +158,20 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: Some(root_key.clone()),
+		},
+		dex: DexConfig {
+			assets,
+			initial_amount,
+			// 0.05% for stable pairs, 0.3% for most pairs and 1% for volatile pairs.
+			fee_tiers: vec![
+				Permill::from_parts(500),
+				Permill::from_parts(3_000),
+				Permill::from_percent(1),
+			],
+			// The sudo account fills limit orders once its key is inserted with the dexk type.
+			keepers: vec![root_key],
 		},
-		dex: DexConfig { assets, initial_amount },
 		transaction_payment: Default::default(),
 		assets: Default::default(),
 	}
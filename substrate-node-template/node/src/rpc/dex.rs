@@ -0,0 +1,234 @@
+//! RPC methods of the dex, answered by the `DexApi` runtime API at a given block.
+
+use std::{convert::TryFrom, marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use dex_primitives::{DexApi as DexRuntimeApi, PoolInfo};
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, FixedU128};
+
+/// Price of a fungible asset in currency and of the currency in the asset, fees excluded.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotPrice {
+	/// Price of the asset in currency.
+	pub asset_price: FixedU128,
+	/// Price of the currency in the asset.
+	pub currency_price: FixedU128,
+}
+
+/// Dex RPC methods. Balances are sent and returned as numbers or hex strings, so that they
+/// are not truncated by JavaScript clients.
+#[rpc(client, server)]
+pub trait DexApi<BlockHash, AssetKind, AssetId, Balance> {
+	/// The pool of a pair, in any order.
+	#[method(name = "dex_getPool")]
+	fn get_pool(
+		&self,
+		asset_a: AssetKind,
+		asset_b: AssetKind,
+		at: Option<BlockHash>,
+	) -> RpcResult<PoolInfo<AssetKind, AssetId, NumberOrHex>>;
+
+	/// Every pool of the dex.
+	#[method(name = "dex_listPools")]
+	fn list_pools(
+		&self,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<PoolInfo<AssetKind, AssetId, NumberOrHex>>>;
+
+	/// Amount received by swapping `amount` along `path`, or with `exact_out` the amount to
+	/// swap along `path` to receive `amount`. Fees are included.
+	#[method(name = "dex_quote")]
+	fn quote(
+		&self,
+		path: Vec<AssetKind>,
+		amount: NumberOrHex,
+		exact_out: bool,
+		at: Option<BlockHash>,
+	) -> RpcResult<NumberOrHex>;
+
+	/// Spot price of a fungible asset at the given block, the best block by default.
+	#[method(name = "dex_priceAt")]
+	fn price_at(&self, asset_id: AssetId, at: Option<BlockHash>) -> RpcResult<SpotPrice>;
+}
+
+/// Error codes of the dex RPC methods.
+pub enum Error {
+	/// The runtime API call failed.
+	RuntimeError,
+	/// There is no pool for the requested pair.
+	PoolNotFound,
+	/// An amount does not fit in a balance, or the pools cannot cover it.
+	Overflow,
+	/// The swap path has less than two assets.
+	InvalidPath,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::PoolNotFound => 2,
+			Error::Overflow => 3,
+			Error::InvalidPath => 4,
+		}
+	}
+}
+
+fn error(code: Error, message: &str, data: Option<String>) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(code.into(), message, data)).into()
+}
+
+fn runtime_error(e: sp_api::ApiError) -> JsonRpseeError {
+	error(Error::RuntimeError, "Unable to query the dex.", Some(e.to_string()))
+}
+
+fn pool_not_found() -> JsonRpseeError {
+	error(Error::PoolNotFound, "Pool not found.", None)
+}
+
+fn overflow() -> JsonRpseeError {
+	error(Error::Overflow, "The amount overflows or the pools cannot cover it.", None)
+}
+
+fn to_rpc_pool<AssetKind, AssetId, Balance: Into<NumberOrHex>>(
+	pool: PoolInfo<AssetKind, AssetId, Balance>,
+) -> PoolInfo<AssetKind, AssetId, NumberOrHex> {
+	PoolInfo {
+		asset_a: pool.asset_a,
+		asset_b: pool.asset_b,
+		reserve_a: pool.reserve_a.into(),
+		reserve_b: pool.reserve_b.into(),
+		liquidity_asset_id: pool.liquidity_asset_id,
+		liquidity_supply: pool.liquidity_supply.into(),
+		fee: pool.fee,
+		paused: pool.paused,
+	}
+}
+
+/// Provides the dex RPC methods.
+pub struct Dex<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Dex<C, Block> {
+	/// Creates a new instance of the dex RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AssetKind, AssetId, Balance>
+	DexApiServer<<Block as BlockT>::Hash, AssetKind, AssetId, Balance> for Dex<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DexRuntimeApi<Block, AssetKind, AssetId, Balance>,
+	AssetKind: Codec + Clone + Send + Sync + 'static + Serialize + for<'de> Deserialize<'de>,
+	AssetId: Codec + Send + Sync + 'static + Serialize + for<'de> Deserialize<'de>,
+	Balance: Codec + TryFrom<NumberOrHex> + Into<NumberOrHex> + Send + Sync + 'static,
+{
+	fn get_pool(
+		&self,
+		asset_a: AssetKind,
+		asset_b: AssetKind,
+		at: Option<Block::Hash>,
+	) -> RpcResult<PoolInfo<AssetKind, AssetId, NumberOrHex>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let pool = api.get_pool(&at, asset_a, asset_b).map_err(runtime_error)?;
+		pool.map(to_rpc_pool).ok_or_else(pool_not_found)
+	}
+
+	fn list_pools(
+		&self,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Vec<PoolInfo<AssetKind, AssetId, NumberOrHex>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let pools = api.list_pools(&at).map_err(runtime_error)?;
+		Ok(pools.into_iter().map(to_rpc_pool).collect())
+	}
+
+	fn quote(
+		&self,
+		path: Vec<AssetKind>,
+		amount: NumberOrHex,
+		exact_out: bool,
+		at: Option<Block::Hash>,
+	) -> RpcResult<NumberOrHex> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		if path.len() < 2 {
+			return Err(error(Error::InvalidPath, "The path needs at least two assets.", None))
+		}
+		let amount = Balance::try_from(amount).map_err(|_| overflow())?;
+
+		// tell a missing pool apart from a swap that cannot be calculated
+		for hop in path.windows(2) {
+			api.get_pool(&at, hop[0].clone(), hop[1].clone())
+				.map_err(runtime_error)?
+				.ok_or_else(pool_not_found)?;
+		}
+
+		let quote = if exact_out {
+			api.quote_exact_out(&at, path, amount)
+		} else {
+			api.quote_exact_in(&at, path, amount)
+		};
+		quote.map_err(runtime_error)?.map(Into::into).ok_or_else(overflow)
+	}
+
+	fn price_at(&self, asset_id: AssetId, at: Option<Block::Hash>) -> RpcResult<SpotPrice> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let (asset_price, currency_price) = api
+			.spot_price(&at, asset_id)
+			.map_err(runtime_error)?
+			.ok_or_else(pool_not_found)?;
+		Ok(SpotPrice { asset_price, currency_price })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn code(e: JsonRpseeError) -> i32 {
+		match e {
+			JsonRpseeError::Call(CallError::Custom(object)) => object.code(),
+			e => panic!("unexpected error {:?}", e),
+		}
+	}
+
+	#[test]
+	fn error_codes_are_stable() {
+		assert_eq!(i32::from(Error::RuntimeError), 1);
+		assert_eq!(i32::from(Error::PoolNotFound), 2);
+		assert_eq!(i32::from(Error::Overflow), 3);
+		assert_eq!(i32::from(Error::InvalidPath), 4);
+	}
+
+	#[test]
+	fn errors_carry_their_code() {
+		let e = runtime_error(sp_api::ApiError::Application("failed".into()));
+		assert_eq!(code(e), 1);
+		assert_eq!(code(pool_not_found()), 2);
+		assert_eq!(code(overflow()), 3);
+		assert_eq!(code(error(Error::InvalidPath, "Invalid path.", None)), 4);
+	}
+}
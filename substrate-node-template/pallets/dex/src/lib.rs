@@ -1,5 +1,19 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
+use frame_support::{
+	sp_runtime::{
+		traits::{DispatchInfoOf, SaturatedConversion, SignedExtension},
+		transaction_validity::{
+			InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+		},
+		DispatchError, KeyTypeId,
+	},
+	traits::IsSubType,
+};
+use scale_info::TypeInfo;
+use sp_std::{fmt::Debug, marker::PhantomData};
+
 pub use pallet::*;
 
 #[cfg(test)]
@@ -11,28 +25,103 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
+pub mod weights;
+
+/// Receives the reserves lent by `flash_swap`. Implemented by the pallets that arbitrageurs and
+/// liquidators use, so that they can act with the borrowed amounts within the same call.
+pub trait FlashSwapHandler<AccountId, AssetKind, Balance> {
+	/// Called after `amount_a` of `asset_a` and `amount_b` of `asset_b` were transferred to the
+	/// `borrower`, with the `data` given to `flash_swap`. Returns the amounts of `asset_a` and
+	/// `asset_b` the borrower repays, which the dex takes from it right after the call. While
+	/// the call lasts the pool cannot be used.
+	fn on_flash_swap(
+		borrower: &AccountId,
+		asset_a: &AssetKind,
+		asset_b: &AssetKind,
+		amount_a: Balance,
+		amount_b: Balance,
+		data: &[u8],
+	) -> Result<(Balance, Balance), DispatchError>;
+}
+
+/// Disables flash swaps.
+impl<AccountId, AssetKind, Balance> FlashSwapHandler<AccountId, AssetKind, Balance> for () {
+	fn on_flash_swap(
+		_borrower: &AccountId,
+		_asset_a: &AssetKind,
+		_asset_b: &AssetKind,
+		_amount_a: Balance,
+		_amount_b: Balance,
+		_data: &[u8],
+	) -> Result<(Balance, Balance), DispatchError> {
+		Err(DispatchError::Other("flash swaps are disabled"))
+	}
+}
+
+/// Key type of the keepers that submit the fillable limit orders from the offchain worker.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"dexk");
+
+/// Keys the keepers sign their payloads with.
+pub mod crypto {
+	use super::KEY_TYPE;
+	use frame_support::sp_runtime::{
+		app_crypto::{app_crypto, sr25519},
+		MultiSignature, MultiSigner,
+	};
+
+	app_crypto!(sr25519, KEY_TYPE);
+
+	/// Signs the keeper payloads with the sr25519 keys of `KEY_TYPE` in the keystore.
+	pub struct KeeperId;
+
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for KeeperId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sr25519::Signature;
+		type GenericPublic = sr25519::Public;
+	}
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 
 	//imports
+	use crate::{weights, FlashSwapHandler};
 	use codec::EncodeLike;
+	use dex_primitives::PoolInfo;
 	use frame_support::{
+		log,
 		pallet_prelude::{DispatchResult, *},
 		sp_runtime::{
 			traits::{
-				AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero,
+				AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Hash as HashT,
+				IdentifyAccount, One, Saturating, Zero,
 			},
-			FixedPointOperand,
+			FixedPointOperand, SaturatedConversion,
 		},
+		storage::with_storage_layer,
 		traits::{
 			fungibles::{self, *},
 			tokens::Balance,
-			Currency, ExistenceRequirement, LockableCurrency, ReservableCurrency,
+			BalanceStatus, Currency, ExistenceRequirement, LockableCurrency, ReservableCurrency,
+			UnixTime,
 		},
 		PalletId,
 	};
-	use frame_system::pallet_prelude::{OriginFor, *};
-	use sp_std::fmt::Debug;
+	use frame_system::{
+		offchain::{
+			AppCrypto, SendTransactionTypes, SendUnsignedTransaction, SignedPayload, Signer,
+			SigningTypes,
+		},
+		pallet_prelude::{OriginFor, *},
+	};
+	#[cfg(feature = "std")]
+	use serde::{Deserialize, Serialize};
+	use sp_arithmetic::{
+		helpers_128bit::multiply_by_rational_with_rounding, FixedPointNumber, FixedU128, PerThing,
+		Permill, Rounding,
+	};
+	use sp_std::{fmt::Debug, vec, vec::Vec};
 
 	//types
 	pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
@@ -40,14 +129,24 @@ pub mod pallet {
 	pub type AssetIdOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<
 		<T as frame_system::Config>::AccountId,
 	>>::AssetId;
+	pub type AssetKindOf<T> = AssetKind<AssetIdOf<T>>;
+	pub type PoolIdOf<T> = (AssetKindOf<T>, AssetKindOf<T>);
+	pub type PoolInfoOf<T> = PoolInfo<AssetKindOf<T>, AssetIdOf<T>, BalanceOf<T>>;
+
+	//storage version, bumped to 1 when pools were keyed by a pair of assets and got their own
+	//fee, status and cumulative prices
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	///pallet configuration
 	#[pallet::config]
-	pub trait Config: frame_system::Config {
+	pub trait Config:
+		frame_system::Config + SendTransactionTypes<Call<Self>> + SigningTypes
+	{
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
@@ -63,6 +162,7 @@ pub mod pallet {
 				Balance = Self::AssetBalance,
 			> + fungibles::Mutate<Self::AccountId>
 			+ fungibles::InspectMetadata<Self::AccountId>
+			+ fungibles::metadata::Mutate<Self::AccountId>
 			+ fungibles::Transfer<Self::AccountId>
 			+ fungibles::Create<Self::AccountId>
 			+ fungibles::Destroy<Self::AccountId>;
@@ -81,42 +181,341 @@ pub mod pallet {
 			+ Clone
 			+ Debug
 			+ PartialEq
+			+ Ord
+			+ One
+			+ CheckedAdd
 			+ EncodeLike
 			+ Decode;
 
 		#[pallet::constant]
 		type PalletId: Get<PalletId>;
 
-		//types to manage the percentage fee
+		//maximum number of fee tiers that pools can choose from
+		#[pallet::constant]
+		type MaxFeeTiers: Get<u32>;
+
+		//account that receives the protocol share of the swap fees
+		type ProtocolFeeRecipient: Get<Self::AccountId>;
+
+		//upper bound of the max_hops parameter, maximum number of assets in a swap path
+		//including the first and the last one
+		#[pallet::constant]
+		type MaxHops: Get<u32>;
+
+		//origin allowed to update the dex parameters and fee tiers
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		//default of the min_liquidity parameter, liquidity locked forever in every new pool
+		#[pallet::constant]
+		type MinimumLiquidity: Get<BalanceOf<Self>>;
+
+		//first id of the range reserved for liquidity assets, which are allocated in order
+		#[pallet::constant]
+		type FirstLiquidityAssetId: Get<Self::AssetId>;
+
+		//decimals of the currency, used by the liquidity assets of currency pools
+		#[pallet::constant]
+		type NativeDecimals: Get<u8>;
+
+		//maximum length of the name and symbol of the liquidity assets, the StringLimit of the
+		//assets pallet
+		#[pallet::constant]
+		type StringLimit: Get<u32>;
+
+		//account that receives the reserves left in a pool when it is destroyed
+		type DustRecipient: Get<Self::AccountId>;
+
+		//blocks between the admin scheduling the destruction of a pool that still has liquidity
+		//providers and the pool being destroyed, which gives them time to withdraw
+		#[pallet::constant]
+		type DestroyGracePeriod: Get<Self::BlockNumber>;
+
+		//maximum number of liquidity asset accounts and approvals removed by destroy_pool, the
+		//rest can be removed through the calls of the assets pallet to finish the destruction
+		#[pallet::constant]
+		type DestroyItemsLimit: Get<u32>;
+
+		//origin allowed to create pools, which returns the account of the creator
+		type PoolCreateOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+
+		//currency reserved from the creator of a pool and refunded when the pool is destroyed
+		#[pallet::constant]
+		type PoolCreationDeposit: Get<BalanceOf<Self>>;
+
+		//when true, pools can only be created with the fungible assets allowed by the admin
+		#[pallet::constant]
+		type RestrictListings: Get<bool>;
+
+		//origin allowed to pause and unpause pools
+		type PauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		//time source of the price oracle, the timestamp pallet in the runtime
+		type UnixTime: UnixTime;
+
+		//maximum number of cumulative price observations kept for a pool
+		#[pallet::constant]
+		type MaxObservations: Get<u32>;
+
+		//receives the reserves lent by flash_swap, () disables flash swaps
+		type FlashSwapHandler: FlashSwapHandler<Self::AccountId, AssetKindOf<Self>, BalanceOf<Self>>;
+
+		//maximum number of limit orders waiting to sell an asset of a pool, and of limit orders
+		//expiring at the same block
+		#[pallet::constant]
+		type MaxOrdersPerBook: Get<u32>;
+
+		//keys of the keepers that submit the fillable limit orders from the offchain worker
+		type KeeperAuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+		//share of the input of a limit order paid to the keeper that fills it
+		#[pallet::constant]
+		type KeeperFee: Get<Permill>;
+
+		//priority of the keeper transactions
+		#[pallet::constant]
+		type KeeperPriority: Get<TransactionPriority>;
+
+		//maximum number of dca schedules executed at the same block
+		#[pallet::constant]
+		type MaxDcaPerBlock: Get<u32>;
+
+		//currency reserved by every dca schedule, given back when it ends
+		#[pallet::constant]
+		type DcaDeposit: Get<BalanceOf<Self>>;
+
+		//number of consecutive periods a dca schedule can skip before it ends
+		#[pallet::constant]
+		type MaxDcaSkips: Get<u32>;
+
+		//currency reserved by commit_swap, slashed if the swap is not revealed in time
+		#[pallet::constant]
+		type CommitBond: Get<BalanceOf<Self>>;
+
+		//number of blocks after a commitment during which the swap can be revealed
 		#[pallet::constant]
-		type Thousand: Get<BalanceOf<Self>>;
+		type RevealWindow: Get<Self::BlockNumber>;
 
+		//maximum number of commitments made at the same block
 		#[pallet::constant]
-		type Fee: Get<BalanceOf<Self>>;
+		type MaxCommitsPerBlock: Get<u32>;
+
+		//maximum number of swaps submitted to a pool in batch mode at the same block
+		#[pallet::constant]
+		type MaxBatchSwaps: Get<u32>;
+	}
+
+	//one side of a pool, either the native currency or a fungible asset
+	#[derive(
+		Clone, Encode, Decode, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug, MaxEncodedLen, TypeInfo,
+	)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum AssetKind<AssetId> {
+		Native,
+		Asset(AssetId),
+	}
+
+	impl<AssetId> Default for AssetKind<AssetId> {
+		fn default() -> Self {
+			AssetKind::Native
+		}
+	}
+
+	//a paused pool only allows removing liquidity, so providers can always exit
+	#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub enum PoolStatus {
+		Active,
+		Paused,
+	}
+
+	impl Default for PoolStatus {
+		fn default() -> Self {
+			PoolStatus::Active
+		}
 	}
 
+	//a pool always stores its sides ordered (asset_a < asset_b), so the native currency is always
+	//asset_a of a currency pool
 	#[derive(
 		Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default, MaxEncodedLen, TypeInfo,
 	)]
 	pub struct Pool<AssetId, AssetBalance> {
-		pub asset_id: AssetId,
-		pub currency_reserve: AssetBalance,
-		pub asset_reserve: AssetBalance,
+		pub asset_a: AssetKind<AssetId>,
+		pub asset_b: AssetKind<AssetId>,
+		pub reserve_a: AssetBalance,
+		pub reserve_b: AssetBalance,
 		pub liquidity_asset_id: AssetId,
+		pub fee: Permill,
+		pub status: PoolStatus,
+		//sum of the price of each side, in units of the other side, multiplied by the seconds
+		//that price lasted. They wrap on overflow, only the difference between two values matters
+		pub price_a_cumulative: FixedU128,
+		pub price_b_cumulative: FixedU128,
+		//last time the cumulative prices were updated, in seconds
+		pub last_timestamp: u64,
 	}
 
 	type PoolOf<T> = Pool<AssetIdOf<T>, BalanceOf<T>>;
 
+	//parameters of the dex that can be updated without a runtime upgrade
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct Parameters<AssetBalance> {
+		//fee of the pools created without choosing a fee tier
+		pub default_fee: Permill,
+		//share of every swap fee sent to the protocol fee recipient, zero switches it off
+		pub protocol_fee_share: Permill,
+		//maximum number of assets in a swap path, at most Config::MaxHops
+		pub max_hops: u32,
+		//liquidity locked forever in the pallet account when a pool is created
+		pub min_liquidity: AssetBalance,
+		//maximum price impact of a single swap on a pool
+		pub max_price_impact: Permill,
+	}
+
+	pub type ParametersOf<T> = Parameters<BalanceOf<T>>;
+
+	//cumulative prices of a pool at the time of the first update of a block
+	#[derive(
+		Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default, MaxEncodedLen, TypeInfo,
+	)]
+	pub struct Observation<BlockNumber> {
+		pub block_number: BlockNumber,
+		pub timestamp: u64,
+		pub price_a_cumulative: FixedU128,
+		pub price_b_cumulative: FixedU128,
+	}
+
+	pub type ObservationOf<T> = Observation<BlockNumberFor<T>>;
+
+	//position of the newest observation in the ring buffer of a pool, and the number of
+	//observations the buffer grows to before it starts overwriting the oldest one
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct ObservationState {
+		pub index: u32,
+		pub cardinality_next: u32,
+	}
+
+	//sells amount_in of asset_in for at least min_amount_out of asset_out, as soon as the pool
+	//can fill it before expires_at
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct LimitOrder<AccountId, AssetId, AssetBalance, BlockNumber> {
+		pub owner: AccountId,
+		pub asset_in: AssetKind<AssetId>,
+		pub asset_out: AssetKind<AssetId>,
+		pub amount_in: AssetBalance,
+		pub min_amount_out: AssetBalance,
+		pub expires_at: BlockNumber,
+	}
+
+	pub type LimitOrderOf<T> =
+		LimitOrder<AccountIdOf<T>, AssetIdOf<T>, BalanceOf<T>, BlockNumberFor<T>>;
+
+	pub type OrderId = u64;
+
+	//spends amount_per_period of asset_in on asset_out every period blocks until the budget is
+	//spent, each swap getting at least the value of its input at the pool prices less
+	//max_slippage. skipped counts the periods skipped in a row, and deposit is reserved from the
+	//owner until the schedule ends
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct DcaSchedule<AssetId, AssetBalance, BlockNumber> {
+		pub asset_in: AssetKind<AssetId>,
+		pub asset_out: AssetKind<AssetId>,
+		pub amount_per_period: AssetBalance,
+		pub budget: AssetBalance,
+		pub period: BlockNumber,
+		pub max_slippage: Permill,
+		pub next_execution: BlockNumber,
+		pub skipped: u32,
+		pub deposit: AssetBalance,
+	}
+
+	pub type DcaScheduleOf<T> = DcaSchedule<AssetIdOf<T>, BalanceOf<T>, BlockNumberFor<T>>;
+
+	pub type DcaId = u64;
+
+	//why a dca schedule ended
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+	pub enum DcaEnd {
+		BudgetSpent,
+		InsufficientBalance,
+		QueueFull,
+		Cancelled,
+		TooManySkips,
+		PoolDestroyed,
+	}
+
+	//swap hidden behind a commitment, hashed along with its sender and a salt
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+	pub struct SwapParams<AssetKind, AssetBalance> {
+		pub path: Vec<AssetKind>,
+		pub amount_in: AssetBalance,
+		pub min_amount_out: AssetBalance,
+	}
+
+	pub type SwapParamsOf<T> = SwapParams<AssetKindOf<T>, BalanceOf<T>>;
+
+	//bond reserved for a commitment, and the block it was made at
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct SwapCommit<AssetBalance, BlockNumber> {
+		pub bond: AssetBalance,
+		pub committed_at: BlockNumber,
+	}
+
+	pub type SwapCommitOf<T> = SwapCommit<BalanceOf<T>, BlockNumberFor<T>>;
+
+	//swap submitted to a pool in batch mode, the input is held by the pallet until the batch
+	//is cleared
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct BatchSwap<AccountId, AssetId, AssetBalance> {
+		pub who: AccountId,
+		pub asset_in: AssetKind<AssetId>,
+		pub amount_in: AssetBalance,
+		pub min_amount_out: AssetBalance,
+	}
+
+	pub type BatchSwapOf<T> = BatchSwap<AccountIdOf<T>, AssetIdOf<T>, BalanceOf<T>>;
+
+	//number of blocks a keeper transaction stays valid
+	pub const KEEPER_LONGEVITY: u32 = 5;
+
+	//number of taken ids skipped when looking for a free liquidity asset id
+	pub const MAX_LIQUIDITY_ASSET_ID_PROBES: u32 = 32;
+
+	//stands for the symbol of an asset that has none in the liquidity asset metadata
+	pub const UNKNOWN_SYMBOL: &[u8] = b"ASSET";
+
+	//limit orders that a keeper found fillable, signed with its key
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+	pub struct KeeperPayload<Public, BlockNumber> {
+		pub block_number: BlockNumber,
+		pub order_ids: Vec<OrderId>,
+		pub public: Public,
+	}
+
+	impl<T: SigningTypes> SignedPayload<T> for KeeperPayload<T::Public, T::BlockNumber> {
+		fn public(&self) -> T::Public {
+			self.public.clone()
+		}
+	}
+
+	pub type KeeperPayloadOf<T> = KeeperPayload<<T as SigningTypes>::Public, BlockNumberFor<T>>;
+
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub assets: Vec<AssetIdOf<T>>,
 		pub initial_amount: BalanceOf<T>,
+		pub fee_tiers: Vec<Permill>,
+		pub keepers: Vec<AccountIdOf<T>>,
 	}
 
 	#[cfg(feature = "std")]
 	impl<T: Config> Default for GenesisConfig<T> {
 		fn default() -> GenesisConfig<T> {
-			GenesisConfig { assets: vec![], initial_amount: <BalanceOf<T>>::one() }
+			GenesisConfig {
+				assets: vec![],
+				initial_amount: <BalanceOf<T>>::one(),
+				fee_tiers: vec![],
+				keepers: vec![],
+			}
 		}
 	}
 
@@ -142,6 +541,20 @@ pub mod pallet {
 				&T::PalletId::get().into_account_truncating(),
 				self.initial_amount,
 			);
+
+			//set the fee tiers approved at genesis
+			let fee_tiers: BoundedVec<Permill, T::MaxFeeTiers> =
+				self.fee_tiers.clone().try_into().expect("Too many genesis fee tiers");
+			assert!(
+				fee_tiers.iter().all(|fee| fee.deconstruct() < Permill::ACCURACY),
+				"Genesis fee tiers must be lower than 100%"
+			);
+			<FeeTiers<T>>::put(fee_tiers);
+
+			//add the keepers allowed at genesis
+			for keeper in &self.keepers {
+				<Keepers<T>>::insert(keeper, true);
+			}
 		}
 	}
 
@@ -149,30 +562,266 @@ pub mod pallet {
 	#[pallet::storage]
 	#[pallet::getter(fn pools)]
 	pub(super) type PoolsMap<T: Config> =
+		StorageMap<_, Twox64Concat, PoolIdOf<T>, PoolOf<T>, OptionQuery>;
+
+	#[pallet::type_value]
+	pub fn DefaultNextLiquidityAssetId<T: Config>() -> AssetIdOf<T> {
+		T::FirstLiquidityAssetId::get()
+	}
+
+	//id to allocate to the liquidity asset of the next pool
+	#[pallet::storage]
+	#[pallet::getter(fn next_liquidity_asset_id)]
+	pub(super) type NextLiquidityAssetId<T: Config> =
+		StorageValue<_, AssetIdOf<T>, ValueQuery, DefaultNextLiquidityAssetId<T>>;
+
+	//fees approved by governance that a pool can be created with
+	#[pallet::storage]
+	#[pallet::getter(fn fee_tiers)]
+	pub(super) type FeeTiers<T: Config> =
+		StorageValue<_, BoundedVec<Permill, T::MaxFeeTiers>, ValueQuery>;
+
+	//the 0.3% fee charged before the parameters were stored on chain, no protocol fee and no
+	//price impact limit
+	#[pallet::type_value]
+	pub fn DefaultParameters<T: Config>() -> ParametersOf<T> {
+		Parameters {
+			default_fee: Permill::from_parts(3_000),
+			protocol_fee_share: Permill::zero(),
+			max_hops: T::MaxHops::get(),
+			min_liquidity: T::MinimumLiquidity::get(),
+			max_price_impact: Permill::from_percent(100),
+		}
+	}
+
+	#[pallet::storage]
+	#[pallet::getter(fn parameters)]
+	pub(super) type DexParameters<T: Config> =
+		StorageValue<_, ParametersOf<T>, ValueQuery, DefaultParameters<T>>;
+
+	//total protocol fees collected by every pool, in the same order as the sides of the pool
+	#[pallet::storage]
+	#[pallet::getter(fn protocol_fees_collected)]
+	pub(super) type ProtocolFeesCollected<T: Config> =
+		StorageMap<_, Twox64Concat, PoolIdOf<T>, (BalanceOf<T>, BalanceOf<T>), ValueQuery>;
+
+	//block from which the admin can destroy a pool that still has liquidity providers
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled_destruction)]
+	pub(super) type ScheduledDestructions<T: Config> =
+		StorageMap<_, Twox64Concat, PoolIdOf<T>, BlockNumberFor<T>, OptionQuery>;
+
+	//creator of every pool and the deposit reserved from it
+	#[pallet::storage]
+	#[pallet::getter(fn pool_deposit)]
+	pub(super) type PoolDeposits<T: Config> =
+		StorageMap<_, Twox64Concat, PoolIdOf<T>, (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+	//creator of the pool of every liquidity asset and the metadata deposit it paid, given back
+	//once the liquidity asset is destroyed
+	#[pallet::storage]
+	#[pallet::getter(fn metadata_deposit)]
+	pub(super) type MetadataDeposits<T: Config> =
+		StorageMap<_, Twox64Concat, AssetIdOf<T>, (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+	//pools destroyed while they still had liquidity providers, keyed by their liquidity asset,
+	//with the reserves the providers left withdraw their share of
+	#[pallet::storage]
+	#[pallet::getter(fn retired_pool)]
+	pub(super) type RetiredPools<T: Config> =
+		StorageMap<_, Twox64Concat, AssetIdOf<T>, PoolOf<T>, OptionQuery>;
+
+	//removed pools whose liquidity asset still has more accounts or approvals than
+	//DestroyItemsLimit, keyed by it, with the reserves swept once it is destroyed
+	#[pallet::storage]
+	#[pallet::getter(fn destroying_pool)]
+	pub(super) type DestroyingPools<T: Config> =
 		StorageMap<_, Twox64Concat, AssetIdOf<T>, PoolOf<T>, OptionQuery>;
 
+	//fungible assets that pools can be created with when listings are restricted
+	#[pallet::storage]
+	#[pallet::getter(fn asset_allowed)]
+	pub(super) type AllowedAssets<T: Config> =
+		StorageMap<_, Twox64Concat, AssetIdOf<T>, bool, ValueQuery>;
+
+	//ring buffer of the latest observations of every pool, the newest one is at the index of
+	//its ObservationStates entry
+	#[pallet::storage]
+	#[pallet::getter(fn price_observations)]
+	pub(super) type PriceObservations<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		PoolIdOf<T>,
+		BoundedVec<ObservationOf<T>, T::MaxObservations>,
+		ValueQuery,
+	>;
+
+	#[pallet::type_value]
+	pub fn DefaultObservationState() -> ObservationState {
+		ObservationState { index: 0, cardinality_next: 1 }
+	}
+
+	//like in uniswap v3 a pool keeps a single observation until the admin raises its cardinality
+	#[pallet::storage]
+	#[pallet::getter(fn observation_state)]
+	pub(super) type ObservationStates<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		PoolIdOf<T>,
+		ObservationState,
+		ValueQuery,
+		DefaultObservationState,
+	>;
+
+	//pools whose reserves are lent by the flash swaps in progress, they cannot be used until
+	//their borrowers repay. A handler can borrow from other pools while it holds a loan, so
+	//every lent pool stays locked
+	#[pallet::storage]
+	#[pallet::getter(fn flash_swap_locked)]
+	pub(super) type FlashSwapLocks<T: Config> =
+		StorageMap<_, Twox64Concat, PoolIdOf<T>, bool, ValueQuery>;
+
+	//id to give to the next limit order
+	#[pallet::storage]
+	#[pallet::getter(fn next_order_id)]
+	pub(super) type NextOrderId<T: Config> = StorageValue<_, OrderId, ValueQuery>;
+
+	//limit orders waiting to be filled, their input is escrowed
+	#[pallet::storage]
+	#[pallet::getter(fn limit_orders)]
+	pub(super) type LimitOrders<T: Config> =
+		StorageMap<_, Twox64Concat, OrderId, LimitOrderOf<T>, OptionQuery>;
+
+	//ids of the limit orders selling asset_in for asset_out, keyed by (asset_in, asset_out) and
+	//sorted by limit price, the minimum amount out per unit in, from the lowest to the highest
+	#[pallet::storage]
+	#[pallet::getter(fn order_book)]
+	pub(super) type OrderBooks<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		(AssetKindOf<T>, AssetKindOf<T>),
+		BoundedVec<(FixedU128, OrderId), T::MaxOrdersPerBook>,
+		ValueQuery,
+	>;
+
+	//ids of the limit orders expiring at every block
+	#[pallet::storage]
+	#[pallet::getter(fn order_expiries)]
+	pub(super) type OrderExpiries<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BlockNumberFor<T>,
+		BoundedVec<OrderId, T::MaxOrdersPerBook>,
+		ValueQuery,
+	>;
+
+	//accounts of the keepers allowed to submit fillable limit orders
+	#[pallet::storage]
+	#[pallet::getter(fn is_keeper)]
+	pub(super) type Keepers<T: Config> =
+		StorageMap<_, Twox64Concat, AccountIdOf<T>, bool, ValueQuery>;
+
+	//id to give to the next dca schedule
+	#[pallet::storage]
+	#[pallet::getter(fn next_dca_id)]
+	pub(super) type NextDcaId<T: Config> = StorageValue<_, DcaId, ValueQuery>;
+
+	//dca schedules of every account
+	#[pallet::storage]
+	#[pallet::getter(fn dca_schedules)]
+	pub(super) type DcaSchedules<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountIdOf<T>,
+		Twox64Concat,
+		DcaId,
+		DcaScheduleOf<T>,
+		OptionQuery,
+	>;
+
+	//dca schedules to execute at every block
+	#[pallet::storage]
+	#[pallet::getter(fn dca_queue)]
+	pub(super) type DcaQueue<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BlockNumberFor<T>,
+		BoundedVec<(AccountIdOf<T>, DcaId), T::MaxDcaPerBlock>,
+		ValueQuery,
+	>;
+
+	//swaps committed and not revealed yet
+	#[pallet::storage]
+	#[pallet::getter(fn swap_commits)]
+	pub(super) type SwapCommits<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountIdOf<T>,
+		Identity,
+		T::Hash,
+		SwapCommitOf<T>,
+		OptionQuery,
+	>;
+
+	//commitments whose bond is slashed at every block, the first block after their window
+	#[pallet::storage]
+	#[pallet::getter(fn commit_expiries)]
+	pub(super) type CommitExpiries<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BlockNumberFor<T>,
+		BoundedVec<(AccountIdOf<T>, T::Hash), T::MaxCommitsPerBlock>,
+		ValueQuery,
+	>;
+
+	//pools whose swaps are collected during the block and cleared together in on_finalize
+	#[pallet::storage]
+	#[pallet::getter(fn batch_mode)]
+	pub(super) type BatchPools<T: Config> =
+		StorageMap<_, Twox64Concat, PoolIdOf<T>, bool, ValueQuery>;
+
+	//swaps submitted in the current block to every pool in batch mode
+	#[pallet::storage]
+	#[pallet::getter(fn batch_swaps)]
+	pub(super) type BatchSwaps<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		PoolIdOf<T>,
+		BoundedVec<BatchSwapOf<T>, T::MaxBatchSwaps>,
+		ValueQuery,
+	>;
+
+	//when true every pool is paused, no matter its own status
+	#[pallet::storage]
+	#[pallet::getter(fn paused)]
+	pub(super) type DexPaused<T: Config> = StorageValue<_, bool, ValueQuery>;
+
 	//pallet events
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
 		PoolCreated {
-			asset_id: AssetIdOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
 			liquidity_asset_id: AssetIdOf<T>,
+			fee: Permill,
 		},
 
 		LiquidityAdded {
 			provider: T::AccountId,
-			asset_id: AssetIdOf<T>,
-			currency_amount: BalanceOf<T>,
-			asset_amount: BalanceOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
 			liquidity_minted: BalanceOf<T>,
 		},
 
 		LiquidityRemoved {
 			provider: T::AccountId,
-			asset_id: AssetIdOf<T>,
-			currency_amount: BalanceOf<T>,
-			asset_amount: BalanceOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
 			liquidity_amount: BalanceOf<T>,
 		},
 
@@ -197,163 +846,700 @@ pub mod pallet {
 			asset_amount: BalanceOf<T>,
 			asset_amount_received: BalanceOf<T>,
 		},
-	}
-
-	//pallet errors
-	#[pallet::error]
-	pub enum Error<T> {
-		NoneValue,
 
-		StorageOverflow,
+		CurrencyToExactAsset {
+			sender: T::AccountId,
+			asset_id: AssetIdOf<T>,
+			currency_amount: BalanceOf<T>,
+			asset_amount: BalanceOf<T>,
+		},
 
-		//asset not found for the requested asset_id
-		AssetNotFound,
+		AssetToExactCurrency {
+			sender: T::AccountId,
+			asset_id: AssetIdOf<T>,
+			asset_amount: BalanceOf<T>,
+			currency_amount: BalanceOf<T>,
+		},
 
-		//liquidity_asset_id already exists
-		AssetLiquidityIDTaken,
+		AssetToExactAsset {
+			sender: T::AccountId,
+			asset_id_from: AssetIdOf<T>,
+			asset_id_to: AssetIdOf<T>,
+			asset_amount: BalanceOf<T>,
+			asset_amount_received: BalanceOf<T>,
+		},
 
-		//a pool with the requested asset_id is already created
-		PoolAlreadyExists,
+		SwapExecuted {
+			sender: T::AccountId,
+			path: Vec<AssetKindOf<T>>,
+			amounts: Vec<BalanceOf<T>>,
+		},
 
-		//asset input amount cannot be zero
-		AssetAmountZero,
+		FeeTiersUpdated {
+			fee_tiers: Vec<Permill>,
+		},
 
-		//asset_id requested to be created already exists
-		AssetAlreadyExists,
+		ParametersUpdated {
+			parameters: ParametersOf<T>,
+		},
 
-		//pool with the requested asset_id not found
-		PoolNotFound,
+		ProtocolFeeCollected {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			asset: AssetKindOf<T>,
+			amount: BalanceOf<T>,
+		},
 
-		//not enough fungible asset balance
-		InsufficientAssetBalance,
+		PoolDestructionScheduled {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			destroy_at: BlockNumberFor<T>,
+		},
 
-		//not enough currency balance
-		InsufficientCurrencyBalance,
+		PoolDestroyed {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+			dust_a: BalanceOf<T>,
+			dust_b: BalanceOf<T>,
+		},
 
-		//currency amount input equal to zero
-		CurrencyAmountZero,
+		//the pool was removed while it still had liquidity providers, they withdraw their share
+		//with withdraw_retired_liquidity and the last one finishes its destruction
+		PoolRetired {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+			reserve_a: BalanceOf<T>,
+			reserve_b: BalanceOf<T>,
+		},
 
-		//the requested liquidity_asset amount to withdraw is zero
-		LiqAmountZero,
+		RetiredLiquidityWithdrawn {
+			who: AccountIdOf<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+			liquidity_amount: BalanceOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+		},
 
-		//this error occurs when a check_add, checked_sub, checked_mul or
-		//checked_div operation results in an overflow
-		OperationOverflow,
-	}
+		//the liquidity asset of a removed pool has too many accounts or approvals to destroy at
+		//once, continue_pool_destruction destroys the rest and then sweeps the reserves
+		PoolDestructionPending {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+		},
 
-	//pallet calls
-	#[pallet::call]
-	impl<T: Config> Pallet<T> {
-		//create pool
-		#[pallet::call_index(0)]
-		#[pallet::weight(0)]
-		pub fn create_pool(
-			origin: OriginFor<T>,
+		AssetListingUpdated {
 			asset_id: AssetIdOf<T>,
-			liquidity_asset_id: AssetIdOf<T>,
-			currency_amount: BalanceOf<T>,
-			asset_amount: BalanceOf<T>,
-		) -> DispatchResult {
-			//verify origin signature
-			let sender = ensure_signed(origin)?;
+			allowed: bool,
+		},
 
-			//verify that the currency amount is not zero
-			ensure!(!currency_amount.is_zero(), Error::<T>::CurrencyAmountZero);
+		PoolStatusUpdated {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			status: PoolStatus,
+		},
 
-			//verify that liquidity asset_id does not exists
-			ensure!(
-				!(T::Fungibles::asset_exists(liquidity_asset_id.clone())),
-				Error::<T>::AssetAlreadyExists
-			);
+		GlobalPauseUpdated {
+			paused: bool,
+		},
 
-			//verify that the asset_id is created
-			ensure!(T::Fungibles::asset_exists(asset_id.clone()), Error::<T>::AssetNotFound);
+		ObservationCardinalityIncreased {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			cardinality_next: u32,
+		},
 
-			//verify that the asset_id does not have an asociate pool
-			ensure!(
-				PoolsMap::<T>::get::<AssetIdOf<T>>(asset_id.clone()) == None,
-				Error::<T>::PoolAlreadyExists
-			);
+		FlashSwapExecuted {
+			borrower: AccountIdOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			amount_a_out: BalanceOf<T>,
+			amount_b_out: BalanceOf<T>,
+			amount_a_in: BalanceOf<T>,
+			amount_b_in: BalanceOf<T>,
+		},
 
-			//verify that asset_amount is not zero
-			ensure!(!asset_amount.is_zero(), Error::<T>::AssetAmountZero);
+		LimitOrderPlaced {
+			order_id: OrderId,
+			owner: AccountIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			expires_at: BlockNumberFor<T>,
+		},
+
+		LimitOrderExecuted {
+			order_id: OrderId,
+			owner: AccountIdOf<T>,
+			keeper: AccountIdOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+			keeper_fee: BalanceOf<T>,
+		},
+
+		LimitOrderCancelled {
+			order_id: OrderId,
+			owner: AccountIdOf<T>,
+		},
+
+		LimitOrderExpired {
+			order_id: OrderId,
+			owner: AccountIdOf<T>,
+		},
+
+		//the input of an expired order could not be given back, the order stays open until its
+		//owner cancels it
+		LimitOrderExpiryFailed {
+			order_id: OrderId,
+			owner: AccountIdOf<T>,
+			error: DispatchError,
+		},
+
+		KeeperUpdated {
+			who: AccountIdOf<T>,
+			allowed: bool,
+		},
+
+		DcaScheduled {
+			dca_id: DcaId,
+			owner: AccountIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			amount_per_period: BalanceOf<T>,
+			budget: BalanceOf<T>,
+			period: BlockNumberFor<T>,
+		},
+
+		DcaExecuted {
+			dca_id: DcaId,
+			owner: AccountIdOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		},
+
+		//the swap of this period failed, the schedule goes on
+		DcaSkipped {
+			dca_id: DcaId,
+			owner: AccountIdOf<T>,
+			error: DispatchError,
+		},
+
+		DcaTerminated {
+			dca_id: DcaId,
+			owner: AccountIdOf<T>,
+			reason: DcaEnd,
+		},
+
+		SwapCommitted {
+			who: AccountIdOf<T>,
+			commitment: T::Hash,
+			bond: BalanceOf<T>,
+		},
+
+		//the bond is given back, the swap itself emits its own event
+		SwapRevealed {
+			who: AccountIdOf<T>,
+			commitment: T::Hash,
+		},
+
+		//the swap was revealed in time, so the bond is given back, but it could not execute
+		RevealedSwapFailed {
+			who: AccountIdOf<T>,
+			commitment: T::Hash,
+			error: DispatchError,
+		},
+
+		SwapCommitSlashed {
+			who: AccountIdOf<T>,
+			commitment: T::Hash,
+			amount: BalanceOf<T>,
+		},
+
+		BatchModeUpdated {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			enabled: bool,
+		},
+
+		BatchSwapSubmitted {
+			who: AccountIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+		},
+
+		BatchSwapExecuted {
+			who: AccountIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		},
+
+		//the swap did not get its minimum at the clearing price, or its pool cannot be used
+		BatchSwapRefunded {
+			who: AccountIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+		},
+
+		//the input of a batch swap could not be given back and stays in the pallet account
+		BatchSwapRefundFailed {
+			who: AccountIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			error: DispatchError,
+		},
+
+		//price_a is the price of asset_a in units of asset_b paid and received by every swap
+		//of the batch, before the fee
+		BatchCleared {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			price_a: FixedU128,
+			amount_a_in: BalanceOf<T>,
+			amount_b_in: BalanceOf<T>,
+			amount_a_out: BalanceOf<T>,
+			amount_b_out: BalanceOf<T>,
+		},
+	}
+
+	//pallet errors
+	#[pallet::error]
+	pub enum Error<T> {
+		NoneValue,
+
+		StorageOverflow,
+
+		//asset not found for the requested asset_id
+		AssetNotFound,
+
+		//a pool with the requested asset_id is already created
+		PoolAlreadyExists,
+
+		//asset input amount cannot be zero
+		AssetAmountZero,
+
+		//asset_id requested to be created already exists
+		AssetAlreadyExists,
+
+		//pool with the requested asset_id not found
+		PoolNotFound,
+
+		//the deposit is too small to mint any liquidity asset
+		InsufficientLiquidityMinted,
+
+		//the liquidity asset amount is too small to withdraw any reserves
+		InsufficientLiquidityBurned,
+
+		//not enough fungible asset balance
+		InsufficientAssetBalance,
+
+		//not enough currency balance
+		InsufficientCurrencyBalance,
+
+		//currency amount input equal to zero
+		CurrencyAmountZero,
+
+		//the requested liquidity_asset amount to withdraw is zero
+		LiqAmountZero,
+
+		//this error occurs when a check_add, checked_sub, checked_mul or
+		//checked_div operation results in an overflow
+		OperationOverflow,
+
+		//the amount received is lower than the requested minimum or the amount paid is higher
+		//than the requested maximum
+		SlippageExceeded,
+
+		//the current block is past the deadline requested for the operation
+		DeadlineExpired,
+
+		//the requested output amount is equal or greater than the pool reserve
+		InsufficientPoolReserve,
+
+		//both sides of a pool cannot be the same asset
+		IdenticalAssets,
+
+		//a swap path must contain at least two assets
+		InvalidPath,
+
+		//the requested fee is not one of the approved fee tiers
+		FeeTierNotAllowed,
+
+		//a fee tier must be lower than 100%
+		InvalidFeeTier,
+
+		//the default fee must be lower than 100% and max_hops between 2 and Config::MaxHops
+		InvalidParameters,
+
+		//the swap moves the price of a pool more than the max_price_impact parameter allows
+		PriceImpactTooHigh,
+
+		//the pool still has liquidity providers and the admin did not schedule its destruction
+		LiquidityProvidersLeft,
+
+		//the grace period before destroying the pool is not over yet
+		GracePeriodNotOver,
+
+		//the destruction of the pool is already scheduled
+		DestructionAlreadyScheduled,
+
+		//liquidity cannot be added to a pool scheduled for destruction
+		PoolBeingDestroyed,
+
+		//there is no pool retired with that liquidity asset
+		RetiredPoolNotFound,
+
+		//there is no removed pool waiting for that liquidity asset to be destroyed
+		DestructionNotPending,
+
+		//listings are restricted and the asset is not in the allow-list
+		AssetNotAllowed,
+
+		//the pool is paused, only removing liquidity is allowed
+		PoolPaused,
+
+		//every pool is paused, only removing liquidity is allowed
+		DexPaused,
+
+		//there is no observation of the pool old enough for the requested window
+		InsufficientPriceHistory,
+
+		//the cardinality must be above the current one and at most Config::MaxObservations
+		InvalidCardinality,
+
+		//the reserves of the pool are lent by a flash swap in progress
+		PoolLocked,
+
+		//the flash swap repayment does not keep the constant product of the pool plus the fee
+		InvariantViolated,
+
+		//the order book of the pool, or the orders expiring at the same block, are full
+		TooManyOrders,
+
+		//limit order not found for the requested order_id
+		OrderNotFound,
+
+		//only the owner of a limit order can cancel it
+		NotOrderOwner,
+
+		//only the keepers added by governance can fill limit orders
+		NotKeeper,
+
+		//a limit order must expire after the current block
+		InvalidExpiry,
+
+		//the period of a dca schedule must not be zero
+		InvalidPeriod,
+
+		//no more dca schedules can be executed at the requested block
+		TooManyDcaSchedules,
+
+		//dca schedule not found for the requested owner and dca_id
+		DcaNotFound,
+
+		//the sender already made the same commitment
+		CommitmentExists,
+
+		//commitment not found for the sender and the revealed swap, or its window is over
+		CommitmentNotFound,
+
+		//a swap can only be revealed after the block it was committed at
+		RevealTooEarly,
+
+		//no more commitments can be made at this block
+		TooManyCommits,
+
+		//the pool clears its swaps in batches, they must be sent with submit_batch_swap
+		PoolInBatchMode,
+
+		//the pool does not clear its swaps in batches
+		PoolNotInBatchMode,
+
+		//no more swaps can be submitted to the pool in this block
+		TooManyBatchSwaps,
+
+		//every id tried for the liquidity asset of a new pool is taken by another asset
+		LiquidityAssetIdUnavailable,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		//refunds the limit orders expiring at this block, executes the dca schedules due and
+		//slashes the commitments whose window is over
+		//also accounts for the batches cleared in on_finalize, which returns no weight
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			Self::expire_limit_orders(n)
+				.saturating_add(Self::execute_dca_schedules(n))
+				.saturating_add(Self::slash_unrevealed_commits(n))
+				.saturating_add(Self::batch_clearing_weight())
+		}
+
+		//clears the swaps submitted to the pools in batch mode during this block
+		fn on_finalize(_n: BlockNumberFor<T>) {
+			for (pool_id, swaps) in <BatchSwaps<T>>::drain() {
+				Self::clear_batch(pool_id, swaps.into_inner());
+			}
+		}
+
+		//submits the limit orders that can be filled at the current pool prices
+		fn offchain_worker(n: BlockNumberFor<T>) {
+			if let Err(error) = Self::submit_fillable_orders(n) {
+				log::debug!(target: "runtime::dex", "keeper: {}", error);
+			}
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		//only accepts keeper payloads signed by a keeper in Keepers that they name, recent enough,
+		//and whose orders can all be filled, so that free transactions cannot be used to spam the
+		//chain
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let (payload, signature) = match call {
+				Call::execute_limit_orders { payload, signature } => (payload, signature),
+				_ => return InvalidTransaction::Call.into(),
+			};
+
+			if !<Keepers<T>>::get(payload.public.clone().into_account()) {
+				return InvalidTransaction::BadSigner.into()
+			}
+			if !SignedPayload::<T>::verify::<T::KeeperAuthorityId>(payload, signature.clone()) {
+				return InvalidTransaction::BadProof.into()
+			}
+			let now = frame_system::Pallet::<T>::block_number();
+			if payload.block_number > now {
+				return InvalidTransaction::Future.into()
+			}
+			if payload.block_number.saturating_add(KEEPER_LONGEVITY.into()) < now {
+				return InvalidTransaction::Stale.into()
+			}
+			if payload.order_ids.is_empty() ||
+				payload.order_ids.len() > T::MaxOrdersPerBook::get() as usize
+			{
+				return InvalidTransaction::ExhaustsResources.into()
+			}
+			let fillable = payload.order_ids.iter().all(|order_id| {
+				<LimitOrders<T>>::get(order_id)
+					.map_or(false, |order| Self::is_order_fillable(&order))
+			});
+			if !fillable {
+				return InvalidTransaction::Stale.into()
+			}
+
+			//two keepers cannot fill the same order
+			payload
+				.order_ids
+				.iter()
+				.fold(ValidTransaction::with_tag_prefix("DexKeeper"), |builder, order_id| {
+					builder.and_provides(order_id)
+				})
+				.priority(T::KeeperPriority::get())
+				.longevity(KEEPER_LONGEVITY.into())
+				.propagate(true)
+				.build()
+		}
+	}
+
+	//pallet calls
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		//create pool
+		#[pallet::call_index(0)]
+		#[pallet::weight(0)]
+		pub fn create_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee: Option<Permill>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify the origin is allowed to create pools
+			let sender = T::PoolCreateOrigin::ensure_origin(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
+			//get the id of the pool, both sides must be different
+			let pool_id = Self::pool_id(asset_a.clone(), asset_b.clone())?;
+
+			//verify that the amount of asset_a is not zero
+			Self::ensure_amount_not_zero(&asset_a, amount_a)?;
+
+			//verify that both assets are created
+			Self::ensure_asset_exists(&asset_a)?;
+			Self::ensure_asset_exists(&asset_b)?;
+
+			//verify that both assets can be listed
+			Self::ensure_asset_allowed(&asset_a)?;
+			Self::ensure_asset_allowed(&asset_b)?;
+
+			//verify that the pair does not have an asociate pool
+			ensure!(!<PoolsMap<T>>::contains_key(&pool_id), Error::<T>::PoolAlreadyExists);
+
+			let parameters = <DexParameters<T>>::get();
+
+			//verify the fee is one of the approved tiers, no fee means the default one
+			let fee = match fee {
+				Some(fee) => {
+					ensure!(<FeeTiers<T>>::get().contains(&fee), Error::<T>::FeeTierNotAllowed);
+					fee
+				},
+				None => parameters.default_fee,
+			};
+
+			//verify that the amount of asset_b is not zero
+			Self::ensure_amount_not_zero(&asset_b, amount_b)?;
+
+			//sort the amounts in the same order as the sides of the pool
+			let (amount_a, amount_b) =
+				if asset_a == pool_id.0 { (amount_a, amount_b) } else { (amount_b, amount_a) };
+			let (asset_a, asset_b) = pool_id;
+
+			//set the liquidity asset amount to mint to the liquidity provider
+			//when the pool is created, this amount is the same as the amount provided of asset_a,
+			//which is the currency amount for currency pools
+			let liquidity_to_mint = amount_a.clone();
+
+			//the minimum liquidity is locked forever in the pallet account, so the creator can
+			//never withdraw the whole pool and inflate the share price for the next provider
+			let liquidity_to_lock = parameters.min_liquidity;
+			ensure!(liquidity_to_mint > liquidity_to_lock, Error::<T>::InsufficientLiquidityMinted);
+
+			//reserve the creation deposit, which is refunded when the pool is destroyed
+			let deposit = T::PoolCreationDeposit::get();
+			T::Currency::reserve(&sender, deposit)?;
+			<PoolDeposits<T>>::insert(
+				(asset_a.clone(), asset_b.clone()),
+				(sender.clone(), deposit),
+			);
 
 			//create liquidity token
-			Self::create_asset_helper(liquidity_asset_id.clone())?;
+			let liquidity_asset_id = Self::create_liquidity_asset()?;
 
 			//create pool
 			let pool = Pool {
-				asset_id: asset_id.clone(),
-				currency_reserve: <BalanceOf<T>>::zero(),
-				asset_reserve: <BalanceOf<T>>::zero(),
+				asset_a: asset_a.clone(),
+				asset_b: asset_b.clone(),
+				reserve_a: <BalanceOf<T>>::zero(),
+				reserve_b: <BalanceOf<T>>::zero(),
 				liquidity_asset_id: liquidity_asset_id.clone(),
+				fee,
+				status: PoolStatus::Active,
+				price_a_cumulative: FixedU128::zero(),
+				price_b_cumulative: FixedU128::zero(),
+				last_timestamp: 0,
 			};
 
-			//set the liquidity asset amount to mint to the liquidity provider
-			//when the pool is created, this amount is the same as the currency amount provided
-			let liquidity_to_mint = currency_amount.clone();
-
 			//add liquidity to the new pool
 			Self::add_liquidity_helper(
 				pool,
-				currency_amount,
-				asset_amount,
-				liquidity_to_mint,
-				sender,
+				amount_a,
+				amount_b,
+				liquidity_to_mint - liquidity_to_lock,
+				sender.clone(),
+			)?;
+
+			//lock the minimum liquidity
+			if !liquidity_to_lock.is_zero() {
+				T::Fungibles::mint_into(
+					liquidity_asset_id.clone(),
+					&Self::account_id(),
+					liquidity_to_lock,
+				)?;
+			}
+
+			//name the liquidity token, once the pallet account holds the reserves
+			Self::set_liquidity_asset_metadata(
+				&sender,
+				liquidity_asset_id.clone(),
+				&asset_a,
+				&asset_b,
 			)?;
 
 			//deposit event
-			Self::deposit_event(Event::PoolCreated { asset_id, liquidity_asset_id });
+			Self::deposit_event(Event::PoolCreated { asset_a, asset_b, liquidity_asset_id, fee });
 
 			Ok(())
 		}
 
+		//rounding policy: every proportional amount is computed as (a * b) / c on 128-bit values
+		//with 256-bit wide intermediates, see multiply_by_rational, and rounded in favour of the
+		//pool. When adding liquidity the asset_b amount taken from the provider is rounded up and
+		//the liquidity minted is rounded down; when removing liquidity both withdrawn amounts are
+		//rounded down.
 		#[pallet::call_index(1)]
 		#[pallet::weight(0)]
 		pub fn add_liquidity(
 			origin: OriginFor<T>,
-			asset_id: AssetIdOf<T>,
-			currency_amount: BalanceOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			amount_a: BalanceOf<T>,
+			max_amount_b: BalanceOf<T>,
+			min_liquidity_minted: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			//verify origin signature
 			let sender = ensure_signed(origin)?;
 
-			//verify that the currency amount is not zero
-			ensure!(!currency_amount.is_zero(), Error::<T>::CurrencyAmountZero);
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
+			//verify that the amount of asset_a is not zero
+			Self::ensure_amount_not_zero(&asset_a, amount_a)?;
 
 			//verify the pool exists
-			let pool = <PoolsMap<T>>::get(asset_id.clone()).ok_or(Error::<T>::PoolNotFound)?;
+			let pool = Self::get_pool(asset_a.clone(), asset_b)?;
+
+			//verify the pool is not about to be destroyed
+			ensure!(
+				!<ScheduledDestructions<T>>::contains_key((
+					pool.asset_a.clone(),
+					pool.asset_b.clone()
+				)),
+				Error::<T>::PoolBeingDestroyed
+			);
 
 			//get the total issuance of liquidity token from the pool
 			let asset_total_issuance =
 				T::Fungibles::total_issuance(pool.liquidity_asset_id.clone());
 
-			//calculate the asset amount starting from de currency amount
-			let currency_amount = currency_amount.clone();
-			let pool_currency_reserve = pool.currency_reserve.clone();
+			//get the reserves in the order requested by the provider
+			let (reserve_a, reserve_b) = Self::get_reserves(&pool, &asset_a);
 
-			let currency_div_currency_reserve = currency_amount
-				.checked_div(&pool_currency_reserve)
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//amount_b = amount_a * reserve_b / reserve_a, rounded up
+			let amount_b =
+				Self::multiply_by_rational(amount_a, reserve_b, reserve_a, Rounding::Up)?;
 
-			let asset_amount = currency_div_currency_reserve
-				.checked_mul(&pool.asset_reserve.clone())
-				.ok_or(Error::<T>::OperationOverflow)?
-				.checked_add(&One::one())
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//liquidity_to_mint = amount_a * total_issuance / reserve_a, rounded down
+			let liquidity_to_mint = Self::multiply_by_rational(
+				amount_a,
+				asset_total_issuance,
+				reserve_a,
+				Rounding::Down,
+			)?;
 
-			let liquidity_to_mint = currency_div_currency_reserve
-				.checked_mul(&asset_total_issuance)
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//verify the deposit is big enough to mint liquidity
+			ensure!(!liquidity_to_mint.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+
+			//verify the price did not move against the provider
+			ensure!(amount_b <= max_amount_b, Error::<T>::SlippageExceeded);
+			ensure!(liquidity_to_mint >= min_liquidity_minted, Error::<T>::SlippageExceeded);
+
+			//sort the amounts in the same order as the sides of the pool
+			let (amount_a, amount_b) =
+				if asset_a == pool.asset_a { (amount_a, amount_b) } else { (amount_b, amount_a) };
 
 			//add liquidity to the new pool
-			Self::add_liquidity_helper(
-				pool,
-				currency_amount,
-				asset_amount,
-				liquidity_to_mint,
-				sender,
-			)?;
+			Self::add_liquidity_helper(pool, amount_a, amount_b, liquidity_to_mint, sender)?;
 
 			Ok(())
 		}
@@ -362,44 +1548,47 @@ pub mod pallet {
 		#[pallet::weight(0)]
 		pub fn remove_liquidity(
 			origin: OriginFor<T>,
-			asset_id: AssetIdOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
 			liquidity_amount: BalanceOf<T>,
+			min_amount_a: BalanceOf<T>,
+			min_amount_b: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			//verify origin signature
 			let sender = ensure_signed(origin)?;
 
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
 			//verify the liq amount is not zero
 			ensure!(!liquidity_amount.is_zero(), Error::<T>::LiqAmountZero);
 
 			//search the pool
-			let pool = <PoolsMap<T>>::get(asset_id.clone()).ok_or(Error::<T>::PoolNotFound)?;
+			let pool = Self::get_pool(asset_a.clone(), asset_b)?;
 
-			//get the total issuance of liquidity asset from the pool
-			let asset_total_issuance =
-				T::Fungibles::total_issuance(pool.liquidity_asset_id.clone());
+			//share of both reserves the liquidity is worth
+			let (amount_a, amount_b) = Self::liquidity_value(&pool, liquidity_amount)?;
 
-			//perform the calculation of the asset amount and currency amount to withdraw
-			let liquidity_amount_div = liquidity_amount
-				.checked_div(&asset_total_issuance)
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//verify the liquidity burnt is worth something
+			ensure!(
+				!amount_a.is_zero() || !amount_b.is_zero(),
+				Error::<T>::InsufficientLiquidityBurned
+			);
 
-			let currency_amount = liquidity_amount_div
-				.checked_mul(&pool.currency_reserve.clone())
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//sort the requested minimums in the same order as the sides of the pool
+			let (min_amount_a, min_amount_b) = if asset_a == pool.asset_a {
+				(min_amount_a, min_amount_b)
+			} else {
+				(min_amount_b, min_amount_a)
+			};
 
-			//final asset amount to withdraw from the pool
-			let asset_amount = liquidity_amount_div
-				.checked_mul(&pool.asset_reserve.clone())
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//verify the provider receives at least the requested amounts
+			ensure!(amount_a >= min_amount_a, Error::<T>::SlippageExceeded);
+			ensure!(amount_b >= min_amount_b, Error::<T>::SlippageExceeded);
 
 			//call remove liquidity helper
-			Self::remove_liquidity_helper(
-				pool,
-				currency_amount,
-				asset_amount,
-				liquidity_amount,
-				sender,
-			)?;
+			Self::remove_liquidity_helper(pool, amount_a, amount_b, liquidity_amount, sender)?;
 
 			Ok(())
 		}
@@ -410,9 +1599,14 @@ pub mod pallet {
 			origin: OriginFor<T>,
 			currency_amount: BalanceOf<T>,
 			asset_id: AssetIdOf<T>,
+			min_asset_out: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			//verify origin signature
 			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
 			let pallet_account = Self::account_id();
 
 			//verify the currency amount is not zero
@@ -422,15 +1616,20 @@ pub mod pallet {
 			ensure!((T::Fungibles::asset_exists(asset_id.clone())), Error::<T>::AssetNotFound);
 
 			//verify the pool exists
-			let mut pool = <PoolsMap<T>>::get(asset_id.clone()).ok_or(Error::<T>::PoolNotFound)?;
+			let pool = Self::get_pool(AssetKind::Native, AssetKind::Asset(asset_id.clone()))?;
+			let (currency_reserve, asset_reserve) = Self::get_reserves(&pool, &AssetKind::Native);
 
 			//call convert helper function
 			let asset_amount = Self::get_input_convert(
 				currency_amount,
-				pool.currency_reserve.clone(),
-				pool.asset_reserve.clone(),
+				currency_reserve,
+				asset_reserve,
+				pool.fee,
 			)?;
 
+			//verify the sender receives at least the requested amount
+			ensure!(asset_amount >= min_asset_out, Error::<T>::SlippageExceeded);
+
 			//transfer currency from sender to pallet
 			T::Currency::transfer(
 				&sender,
@@ -449,17 +1648,7 @@ pub mod pallet {
 			)?;
 
 			//update pool's reserves
-			pool.currency_reserve = pool
-				.currency_reserve
-				.checked_add(&currency_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-			pool.asset_reserve = pool
-				.asset_reserve
-				.checked_sub(&asset_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-
-			//update pool in storage
-			<PoolsMap<T>>::insert(asset_id.clone(), pool);
+			Self::update_swap_reserves(pool, &AssetKind::Native, currency_amount, asset_amount)?;
 
 			//deposit event
 			Self::deposit_event(Event::CurrencyToAsset {
@@ -478,9 +1667,14 @@ pub mod pallet {
 			origin: OriginFor<T>,
 			asset_amount: BalanceOf<T>,
 			asset_id: AssetIdOf<T>,
+			min_currency_out: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			//verify origin signature
 			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
 			let pallet_account = Self::account_id();
 
 			//verify the asset amount is not zero
@@ -490,14 +1684,16 @@ pub mod pallet {
 			ensure!((T::Fungibles::asset_exists(asset_id.clone())), Error::<T>::AssetNotFound);
 
 			//verify the pool exists
-			let mut pool = <PoolsMap<T>>::get(asset_id.clone()).ok_or(Error::<T>::PoolNotFound)?;
+			let asset = AssetKind::Asset(asset_id.clone());
+			let pool = Self::get_pool(AssetKind::Native, asset.clone())?;
+			let (asset_reserve, currency_reserve) = Self::get_reserves(&pool, &asset);
 
 			//call convert helper function
-			let currency_amount = Self::get_input_convert(
-				asset_amount,
-				pool.asset_reserve.clone(),
-				pool.currency_reserve.clone(),
-			)?;
+			let currency_amount =
+				Self::get_input_convert(asset_amount, asset_reserve, currency_reserve, pool.fee)?;
+
+			//verify the sender receives at least the requested amount
+			ensure!(currency_amount >= min_currency_out, Error::<T>::SlippageExceeded);
 
 			//transfer assets from sender to pallet
 			T::Fungibles::transfer(
@@ -517,17 +1713,7 @@ pub mod pallet {
 			)?;
 
 			//update pool's reserves
-			pool.currency_reserve = pool
-				.currency_reserve
-				.checked_sub(&currency_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-			pool.asset_reserve = pool
-				.asset_reserve
-				.checked_add(&asset_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-
-			//update pool in storage
-			<PoolsMap<T>>::insert(asset_id.clone(), pool);
+			Self::update_swap_reserves(pool, &asset, asset_amount, currency_amount)?;
 
 			//deposit event
 			Self::deposit_event(Event::AssetToCurrency {
@@ -547,9 +1733,14 @@ pub mod pallet {
 			asset_id_from: AssetIdOf<T>,
 			asset_id_to: AssetIdOf<T>,
 			asset_amount: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			//verify origin signature
 			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
 			let pallet_account = Self::account_id();
 
 			//verify the asset amount is not zero
@@ -561,27 +1752,16 @@ pub mod pallet {
 			//verify the asset to exists
 			ensure!((T::Fungibles::asset_exists(asset_id_to.clone())), Error::<T>::AssetNotFound);
 
-			//verify the pool of asset from exists
-			let mut pool_from =
-				<PoolsMap<T>>::get(asset_id_from.clone()).ok_or(Error::<T>::PoolNotFound)?;
-
-			//verify the pool of asset to exists
-			let mut pool_to =
-				<PoolsMap<T>>::get(asset_id_to.clone()).ok_or(Error::<T>::PoolNotFound)?;
+			//use the direct pool if it exists, otherwise swap through currency
+			let path = Self::asset_to_asset_path(asset_id_from.clone(), asset_id_to.clone())?;
 
-			//first convert to currency
-			let currency_amount = Self::get_input_convert(
-				asset_amount.clone(),
-				pool_from.asset_reserve.clone(),
-				pool_from.currency_reserve.clone(),
-			)?;
+			//calculate the amounts received at every pool of the path
+			let amounts = Self::get_amounts_out(&path, asset_amount)?;
+			let asset_final_amount =
+				amounts.last().cloned().ok_or(Error::<T>::OperationOverflow)?;
 
-			//then convert from currency to asset
-			let asset_final_amount = Self::get_input_convert(
-				currency_amount,
-				pool_to.currency_reserve.clone(),
-				pool_to.asset_reserve.clone(),
-			)?;
+			//verify the sender receives at least the requested amount
+			ensure!(asset_final_amount >= min_amount_out, Error::<T>::SlippageExceeded);
 
 			//transfer asset_from from sender to pallet
 			T::Fungibles::transfer(
@@ -592,7 +1772,7 @@ pub mod pallet {
 				true,
 			)?;
 
-			//transfer asset_from from sender to pallet
+			//transfer asset_to from pallet to sender
 			T::Fungibles::transfer(
 				asset_id_to.clone(),
 				&pallet_account,
@@ -601,31 +1781,8 @@ pub mod pallet {
 				true,
 			)?;
 
-			//update pool_from reserves
-			pool_from.currency_reserve = pool_from
-				.currency_reserve
-				.checked_sub(&currency_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-			pool_from.asset_reserve = pool_from
-				.asset_reserve
-				.checked_add(&asset_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-
-			//update pool_from in storage
-			<PoolsMap<T>>::insert(asset_id_from.clone(), pool_from);
-
-			//update pool_to reserves
-			pool_to.currency_reserve = pool_to
-				.currency_reserve
-				.checked_add(&currency_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-			pool_to.asset_reserve = pool_to
-				.asset_reserve
-				.checked_sub(&asset_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-
-			//update pool_to in storage
-			<PoolsMap<T>>::insert(asset_id_to.clone(), pool_to);
+			//update the reserves of every pool in the path
+			Self::update_path_reserves(&path, &amounts)?;
 
 			//deposit event
 			Self::deposit_event(Event::AssetToAsset {
@@ -645,208 +1802,2568 @@ pub mod pallet {
 			origin: OriginFor<T>,
 			asset_id: AssetIdOf<T>,
 			asset_amount: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			//verify origin signature
 			let sender = ensure_signed(origin)?;
 
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
 			//mint funds into sender's account
 			T::Fungibles::mint_into(asset_id.clone(), &sender, asset_amount)?;
 
 			Ok(())
 		}
-	}
 
-	//oracle struct
-	#[derive(
-		Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default, MaxEncodedLen, TypeInfo,
-	)]
+		#[pallet::call_index(7)]
+		#[pallet::weight(0)]
+		pub fn currency_to_exact_asset(
+			origin: OriginFor<T>,
+			asset_amount: BalanceOf<T>,
+			asset_id: AssetIdOf<T>,
+			max_currency_in: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+			let pallet_account = Self::account_id();
+
+			//verify the asset amount is not zero
+			ensure!(!asset_amount.is_zero(), Error::<T>::AssetAmountZero);
+
+			//verify the asset exists
+			ensure!((T::Fungibles::asset_exists(asset_id.clone())), Error::<T>::AssetNotFound);
+
+			//verify the pool exists
+			let pool = Self::get_pool(AssetKind::Native, AssetKind::Asset(asset_id.clone()))?;
+			let (currency_reserve, asset_reserve) = Self::get_reserves(&pool, &AssetKind::Native);
+
+			//call convert helper function
+			let currency_amount =
+				Self::get_output_convert(asset_amount, currency_reserve, asset_reserve, pool.fee)?;
+
+			//verify the sender pays at most the requested amount
+			ensure!(currency_amount <= max_currency_in, Error::<T>::SlippageExceeded);
+
+			//transfer currency from sender to pallet
+			T::Currency::transfer(
+				&sender,
+				&pallet_account,
+				currency_amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+
+			//transfer assets from pallet to sender
+			T::Fungibles::transfer(
+				asset_id.clone(),
+				&pallet_account,
+				&sender,
+				asset_amount.clone(),
+				true,
+			)?;
+
+			//update pool's reserves
+			Self::update_swap_reserves(pool, &AssetKind::Native, currency_amount, asset_amount)?;
+
+			//deposit event
+			Self::deposit_event(Event::CurrencyToExactAsset {
+				sender,
+				asset_id,
+				currency_amount,
+				asset_amount,
+			});
+
+			Ok(())
+		}
+
+		#[pallet::call_index(8)]
+		#[pallet::weight(0)]
+		pub fn asset_to_exact_currency(
+			origin: OriginFor<T>,
+			currency_amount: BalanceOf<T>,
+			asset_id: AssetIdOf<T>,
+			max_asset_in: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+			let pallet_account = Self::account_id();
+
+			//verify the currency amount is not zero
+			ensure!(!currency_amount.is_zero(), Error::<T>::CurrencyAmountZero);
+
+			//verify the asset exists
+			ensure!((T::Fungibles::asset_exists(asset_id.clone())), Error::<T>::AssetNotFound);
+
+			//verify the pool exists
+			let asset = AssetKind::Asset(asset_id.clone());
+			let pool = Self::get_pool(AssetKind::Native, asset.clone())?;
+			let (asset_reserve, currency_reserve) = Self::get_reserves(&pool, &asset);
+
+			//call convert helper function
+			let asset_amount = Self::get_output_convert(
+				currency_amount,
+				asset_reserve,
+				currency_reserve,
+				pool.fee,
+			)?;
+
+			//verify the sender pays at most the requested amount
+			ensure!(asset_amount <= max_asset_in, Error::<T>::SlippageExceeded);
+
+			//transfer assets from sender to pallet
+			T::Fungibles::transfer(
+				asset_id.clone(),
+				&sender,
+				&pallet_account,
+				asset_amount.clone(),
+				true,
+			)?;
+
+			//transfer currency from pallet to sender
+			T::Currency::transfer(
+				&pallet_account,
+				&sender,
+				currency_amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+
+			//update pool's reserves
+			Self::update_swap_reserves(pool, &asset, asset_amount, currency_amount)?;
+
+			//deposit event
+			Self::deposit_event(Event::AssetToExactCurrency {
+				sender,
+				asset_id,
+				asset_amount,
+				currency_amount,
+			});
+
+			Ok(())
+		}
+
+		#[pallet::call_index(9)]
+		#[pallet::weight(0)]
+		pub fn asset_to_exact_asset(
+			origin: OriginFor<T>,
+			asset_id_from: AssetIdOf<T>,
+			asset_id_to: AssetIdOf<T>,
+			asset_amount_out: BalanceOf<T>,
+			max_amount_in: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+			let pallet_account = Self::account_id();
+
+			//verify the asset amount is not zero
+			ensure!(!asset_amount_out.is_zero(), Error::<T>::AssetAmountZero);
+
+			//verify the asset from exists
+			ensure!((T::Fungibles::asset_exists(asset_id_from.clone())), Error::<T>::AssetNotFound);
+
+			//verify the asset to exists
+			ensure!((T::Fungibles::asset_exists(asset_id_to.clone())), Error::<T>::AssetNotFound);
+
+			//use the direct pool if it exists, otherwise swap through currency
+			let path = Self::asset_to_asset_path(asset_id_from.clone(), asset_id_to.clone())?;
+
+			//calculate, from the last pool to the first one, the amounts needed at every pool
+			let amounts = Self::get_amounts_in(&path, asset_amount_out)?;
+			let asset_amount = amounts.first().cloned().ok_or(Error::<T>::OperationOverflow)?;
+
+			//verify the sender pays at most the requested amount
+			ensure!(asset_amount <= max_amount_in, Error::<T>::SlippageExceeded);
+
+			//transfer asset_from from sender to pallet
+			T::Fungibles::transfer(
+				asset_id_from.clone(),
+				&sender,
+				&pallet_account,
+				asset_amount.clone(),
+				true,
+			)?;
+
+			//transfer asset_to from pallet to sender
+			T::Fungibles::transfer(
+				asset_id_to.clone(),
+				&pallet_account,
+				&sender,
+				asset_amount_out.clone(),
+				true,
+			)?;
+
+			//update the reserves of every pool in the path
+			Self::update_path_reserves(&path, &amounts)?;
+
+			//deposit event
+			Self::deposit_event(Event::AssetToExactAsset {
+				sender,
+				asset_id_from,
+				asset_id_to,
+				asset_amount,
+				asset_amount_received: asset_amount_out,
+			});
+
+			Ok(())
+		}
+
+		#[pallet::call_index(10)]
+		#[pallet::weight(weights::swap_along_path::<T>(path.len() as u32))]
+		pub fn swap_exact_in_along_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<AssetKindOf<T>, T::MaxHops>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
+			//verify the path goes through at least one pool and is not too long
+			Self::ensure_valid_path(&path)?;
+
+			//verify the input amount is not zero
+			Self::ensure_amount_not_zero(&path[0], amount_in)?;
+
+			//calculate the amounts received at every pool of the path
+			let amounts = Self::get_amounts_out(&path, amount_in)?;
+			let amount_out = amounts.last().cloned().ok_or(Error::<T>::InvalidPath)?;
+
+			//verify the sender receives at least the requested amount
+			ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			//execute every leg of the swap
+			Self::swap_along_path(&sender, &path, &amounts)?;
+
+			//deposit event
+			Self::deposit_event(Event::SwapExecuted { sender, path: path.into_inner(), amounts });
+
+			Ok(())
+		}
+
+		#[pallet::call_index(11)]
+		#[pallet::weight(weights::swap_along_path::<T>(path.len() as u32))]
+		pub fn swap_exact_out_along_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<AssetKindOf<T>, T::MaxHops>,
+			amount_out: BalanceOf<T>,
+			max_amount_in: BalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
+			//verify the path goes through at least one pool and is not too long
+			Self::ensure_valid_path(&path)?;
+
+			//verify the output amount is not zero
+			Self::ensure_amount_not_zero(&path[path.len() - 1], amount_out)?;
+
+			//calculate, from the last pool to the first one, the amounts needed at every pool
+			let amounts = Self::get_amounts_in(&path, amount_out)?;
+			let amount_in = amounts.first().cloned().ok_or(Error::<T>::InvalidPath)?;
+
+			//verify the sender pays at most the requested amount
+			ensure!(amount_in <= max_amount_in, Error::<T>::SlippageExceeded);
+
+			//execute every leg of the swap
+			Self::swap_along_path(&sender, &path, &amounts)?;
+
+			//deposit event
+			Self::deposit_event(Event::SwapExecuted { sender, path: path.into_inner(), amounts });
+
+			Ok(())
+		}
+
+		//replaces the fee tiers that new pools can choose from, existing pools keep their fee
+		#[pallet::call_index(12)]
+		#[pallet::weight(0)]
+		pub fn set_fee_tiers(
+			origin: OriginFor<T>,
+			fee_tiers: BoundedVec<Permill, T::MaxFeeTiers>,
+		) -> DispatchResult {
+			//only governance can approve fee tiers
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			//a fee of 100% would take the whole input
+			ensure!(
+				fee_tiers.iter().all(|fee| fee.deconstruct() < Permill::ACCURACY),
+				Error::<T>::InvalidFeeTier
+			);
+
+			<FeeTiers<T>>::put(fee_tiers.clone());
+
+			//deposit event
+			Self::deposit_event(Event::FeeTiersUpdated { fee_tiers: fee_tiers.into_inner() });
+
+			Ok(())
+		}
+
+		//replaces the tunable parameters of the dex
+		#[pallet::call_index(13)]
+		#[pallet::weight(0)]
+		pub fn set_parameters(origin: OriginFor<T>, parameters: ParametersOf<T>) -> DispatchResult {
+			//only governance can update the parameters
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			//a fee of 100% would take the whole input and a path needs at least two assets
+			ensure!(
+				parameters.default_fee.deconstruct() < Permill::ACCURACY,
+				Error::<T>::InvalidParameters
+			);
+			ensure!(
+				parameters.max_hops >= 2 && parameters.max_hops <= T::MaxHops::get(),
+				Error::<T>::InvalidParameters
+			);
+
+			<DexParameters<T>>::put(parameters.clone());
+
+			//deposit event
+			Self::deposit_event(Event::ParametersUpdated { parameters });
+
+			Ok(())
+		}
+
+		//allows destroying a pool that still has liquidity providers once the grace period is over
+		#[pallet::call_index(14)]
+		#[pallet::weight(0)]
+		pub fn schedule_pool_destruction(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+		) -> DispatchResult {
+			//only governance can retire a pool with liquidity providers
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			//verify the pool exists
+			let pool = Self::get_pool(asset_a, asset_b)?;
+			let pool_id = (pool.asset_a, pool.asset_b);
+
+			ensure!(
+				!<ScheduledDestructions<T>>::contains_key(&pool_id),
+				Error::<T>::DestructionAlreadyScheduled
+			);
+
+			let destroy_at = frame_system::Pallet::<T>::block_number()
+				.saturating_add(T::DestroyGracePeriod::get());
+			<ScheduledDestructions<T>>::insert(&pool_id, destroy_at);
+
+			//deposit event
+			Self::deposit_event(Event::PoolDestructionScheduled {
+				asset_a: pool_id.0,
+				asset_b: pool_id.1,
+				destroy_at,
+			});
+
+			Ok(())
+		}
+
+		//removes a pool without liquidity providers, sending its reserves, the share of the
+		//locked liquidity, to the dust recipient and destroying its liquidity asset. Once its
+		//scheduled destruction is due, a pool that still has providers can also be removed, and
+		//they withdraw their share of the reserves with withdraw_retired_liquidity. The open
+		//limit orders of the pool are given back to their owners
+		#[pallet::call_index(15)]
+		#[pallet::weight(weights::destroy_pool::<T>(
+			2 * T::MaxOrdersPerBook::get(),
+			T::MaxBatchSwaps::get(),
+			T::DestroyItemsLimit::get(),
+		))]
+		pub fn destroy_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+		) -> DispatchResult {
+			//only governance can remove pools, the reserves left are swept away
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			//verify the pool exists and its reserves are not lent
+			let pool = Self::get_pool(asset_a, asset_b)?;
+			Self::ensure_pool_unlocked(&pool)?;
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+
+			let providers_liquidity = Self::providers_liquidity(&pool);
+			if !providers_liquidity.is_zero() {
+				let destroy_at = <ScheduledDestructions<T>>::get(&pool_id)
+					.ok_or(Error::<T>::LiquidityProvidersLeft)?;
+				ensure!(
+					frame_system::Pallet::<T>::block_number() >= destroy_at,
+					Error::<T>::GracePeriodNotOver
+				);
+			}
+
+			//give the open limit orders of the pool back
+			Self::cancel_pool_limit_orders(&pool_id)?;
+
+			//give back the swaps waiting for the batch of the pool
+			<BatchPools<T>>::remove(&pool_id);
+			<BatchSwaps<T>>::take(&pool_id).into_iter().for_each(Self::refund_batch_swap);
+
+			//refund the creation deposit
+			if let Some((creator, deposit)) = <PoolDeposits<T>>::take(&pool_id) {
+				T::Currency::unreserve(&creator, deposit);
+			}
+
+			<PoolsMap<T>>::remove(&pool_id);
+			<ProtocolFeesCollected<T>>::remove(&pool_id);
+			<ScheduledDestructions<T>>::remove(&pool_id);
+			<PriceObservations<T>>::remove(&pool_id);
+			<ObservationStates<T>>::remove(&pool_id);
+
+			if providers_liquidity.is_zero() {
+				return Self::finish_pool_destruction(pool)
+			}
+
+			//the reserves stay in the pallet account until the providers withdraw them
+			<RetiredPools<T>>::insert(&pool.liquidity_asset_id, &pool);
+
+			//deposit event
+			Self::deposit_event(Event::PoolRetired {
+				asset_a: pool.asset_a,
+				asset_b: pool.asset_b,
+				liquidity_asset_id: pool.liquidity_asset_id,
+				reserve_a: pool.reserve_a,
+				reserve_b: pool.reserve_b,
+			});
+
+			Ok(())
+		}
+
+		//adds a fungible asset to the allow-list of listings or removes it, existing pools are
+		//not affected
+		#[pallet::call_index(16)]
+		#[pallet::weight(0)]
+		pub fn set_asset_allowed(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			allowed: bool,
+		) -> DispatchResult {
+			//only governance can approve listings
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			if allowed {
+				<AllowedAssets<T>>::insert(asset_id.clone(), true);
+			} else {
+				<AllowedAssets<T>>::remove(asset_id.clone());
+			}
+
+			//deposit event
+			Self::deposit_event(Event::AssetListingUpdated { asset_id, allowed });
+
+			Ok(())
+		}
+
+		//stops swaps and new liquidity on a pool, providers can still remove their liquidity
+		#[pallet::call_index(17)]
+		#[pallet::weight(0)]
+		pub fn pause_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			Self::set_pool_status(asset_a, asset_b, PoolStatus::Paused)
+		}
+
+		#[pallet::call_index(18)]
+		#[pallet::weight(0)]
+		pub fn unpause_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			Self::set_pool_status(asset_a, asset_b, PoolStatus::Active)
+		}
+
+		//pauses every pool at once, without changing the status of each one
+		#[pallet::call_index(19)]
+		#[pallet::weight(0)]
+		pub fn pause_all(origin: OriginFor<T>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			<DexPaused<T>>::put(true);
+
+			//deposit event
+			Self::deposit_event(Event::GlobalPauseUpdated { paused: true });
+
+			Ok(())
+		}
+
+		//lifts the global pause, pools paused one by one stay paused
+		#[pallet::call_index(20)]
+		#[pallet::weight(0)]
+		pub fn unpause_all(origin: OriginFor<T>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			<DexPaused<T>>::kill();
+
+			//deposit event
+			Self::deposit_event(Event::GlobalPauseUpdated { paused: false });
+
+			Ok(())
+		}
+
+		//grows the observation history of a pool up to cardinality_next observations, the
+		//buffer only grows as new observations are written
+		#[pallet::call_index(21)]
+		#[pallet::weight(0)]
+		pub fn increase_observation_cardinality(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			cardinality_next: u32,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			//verify the pool exists
+			let pool = Self::get_pool(asset_a, asset_b)?;
+			let pool_id = (pool.asset_a, pool.asset_b);
+
+			<ObservationStates<T>>::try_mutate(&pool_id, |state| -> DispatchResult {
+				ensure!(
+					cardinality_next > state.cardinality_next &&
+						cardinality_next <= T::MaxObservations::get(),
+					Error::<T>::InvalidCardinality
+				);
+				state.cardinality_next = cardinality_next;
+				Ok(())
+			})?;
+
+			//deposit event
+			Self::deposit_event(Event::ObservationCardinalityIncreased {
+				asset_a: pool_id.0,
+				asset_b: pool_id.1,
+				cardinality_next,
+			});
+
+			Ok(())
+		}
+
+		//lends amount_a_out and amount_b_out of the pool to the sender, lets the
+		//Config::FlashSwapHandler use them, and takes back the repayment it returns. The whole
+		//call fails unless the constant product of the pool, with the fee charged on the
+		//repayment, does not decrease
+		#[pallet::call_index(22)]
+		#[pallet::weight(0)]
+		pub fn flash_swap(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			amount_a_out: BalanceOf<T>,
+			amount_b_out: BalanceOf<T>,
+			data: Vec<u8>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the operation has not expired
+			Self::ensure_deadline(deadline)?;
+
+			//verify something is borrowed
+			ensure!(
+				!amount_a_out.is_zero() || !amount_b_out.is_zero(),
+				Error::<T>::AssetAmountZero
+			);
+
+			//search the pool
+			let pool = Self::get_pool(asset_a.clone(), asset_b)?;
+			Self::ensure_pool_active(&pool)?;
+			Self::ensure_pool_not_batched(&pool)?;
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+
+			//sort the borrowed amounts in the same order as the sides of the pool
+			let (amount_a_out, amount_b_out) = if asset_a == pool.asset_a {
+				(amount_a_out, amount_b_out)
+			} else {
+				(amount_b_out, amount_a_out)
+			};
+
+			//the pool cannot lend its whole reserves
+			ensure!(
+				amount_a_out < pool.reserve_a && amount_b_out < pool.reserve_b,
+				Error::<T>::InsufficientPoolReserve
+			);
+
+			//lend the amounts to the sender
+			let pallet_account = Self::account_id();
+			for (asset, amount) in [(&pool.asset_a, amount_a_out), (&pool.asset_b, amount_b_out)] {
+				if !amount.is_zero() {
+					Self::transfer_asset_kind(asset, &pallet_account, &sender, amount, false)?;
+				}
+			}
+
+			//the handler uses them while nothing else can touch the pool
+			<FlashSwapLocks<T>>::insert(&pool_id, true);
+			let (amount_a_in, amount_b_in) = T::FlashSwapHandler::on_flash_swap(
+				&sender,
+				&pool.asset_a,
+				&pool.asset_b,
+				amount_a_out,
+				amount_b_out,
+				&data,
+			)?;
+			<FlashSwapLocks<T>>::remove(&pool_id);
+
+			//the pool is read again, so nothing done by the handler can be overwritten
+			let mut pool = Self::get_pool(pool_id.0.clone(), pool_id.1.clone())?;
+
+			//accumulate the price that lasted until now, before the reserves change
+			Self::update_cumulative_prices(&mut pool);
+
+			//take the repayment from the sender
+			for (asset, amount) in [(&pool.asset_a, amount_a_in), (&pool.asset_b, amount_b_in)] {
+				if !amount.is_zero() {
+					Self::transfer_asset_kind(asset, &sender, &pallet_account, amount, true)?;
+				}
+			}
+
+			let reserve_a = pool
+				.reserve_a
+				.checked_sub(&amount_a_out)
+				.and_then(|reserve| reserve.checked_add(&amount_a_in))
+				.ok_or(Error::<T>::OperationOverflow)?;
+			let reserve_b = pool
+				.reserve_b
+				.checked_sub(&amount_b_out)
+				.and_then(|reserve| reserve.checked_add(&amount_b_in))
+				.ok_or(Error::<T>::OperationOverflow)?;
+			Self::ensure_constant_product(&pool, reserve_a, reserve_b, amount_a_in, amount_b_in)?;
+
+			//send the protocol share of the fee paid on the repayment to the recipient
+			let protocol_fee_a = Self::collect_protocol_fee(&pool, &pool.asset_a, amount_a_in);
+			let protocol_fee_b = Self::collect_protocol_fee(&pool, &pool.asset_b, amount_b_in);
+			pool.reserve_a =
+				reserve_a.checked_sub(&protocol_fee_a).ok_or(Error::<T>::OperationOverflow)?;
+			pool.reserve_b =
+				reserve_b.checked_sub(&protocol_fee_b).ok_or(Error::<T>::OperationOverflow)?;
+
+			//update pool in storage
+			<PoolsMap<T>>::insert(&pool_id, pool);
+
+			//deposit event
+			Self::deposit_event(Event::FlashSwapExecuted {
+				borrower: sender,
+				asset_a: pool_id.0,
+				asset_b: pool_id.1,
+				amount_a_out,
+				amount_b_out,
+				amount_a_in,
+				amount_b_in,
+			});
+
+			Ok(())
+		}
+
+		//escrows amount_in of asset_in until the pool of asset_in and asset_out can swap it for at
+		//least min_amount_out, or until the order expires at expires_at
+		#[pallet::call_index(23)]
+		#[pallet::weight(0)]
+		pub fn place_limit_order(
+			origin: OriginFor<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+			expires_at: BlockNumberFor<T>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify both amounts are not zero
+			Self::ensure_amount_not_zero(&asset_in, amount_in)?;
+			Self::ensure_amount_not_zero(&asset_out, min_amount_out)?;
+
+			//verify the pool exists
+			Self::get_pool(asset_in.clone(), asset_out.clone())?;
+
+			ensure!(
+				expires_at > frame_system::Pallet::<T>::block_number(),
+				Error::<T>::InvalidExpiry
+			);
+
+			//limit price = min_amount_out / amount_in
+			let limit_price = FixedU128::checked_from_rational(min_amount_out, amount_in)
+				.ok_or(Error::<T>::OperationOverflow)?;
+
+			let order_id = <NextOrderId<T>>::get();
+
+			//orders with the same limit price are filled in the order they were placed
+			<OrderBooks<T>>::try_mutate((asset_in.clone(), asset_out.clone()), |book| {
+				let position = book.partition_point(|(price, _)| *price <= limit_price);
+				book.try_insert(position, (limit_price, order_id))
+					.map_err(|_| Error::<T>::TooManyOrders)
+			})?;
+			<OrderExpiries<T>>::try_mutate(expires_at, |expiring| {
+				expiring.try_push(order_id).map_err(|_| Error::<T>::TooManyOrders)
+			})?;
+
+			let order = LimitOrder {
+				owner: sender.clone(),
+				asset_in: asset_in.clone(),
+				asset_out: asset_out.clone(),
+				amount_in,
+				min_amount_out,
+				expires_at,
+			};
+			Self::escrow_limit_order(&order)?;
+			<LimitOrders<T>>::insert(order_id, order);
+			<NextOrderId<T>>::put(order_id.saturating_add(1));
+
+			//deposit event
+			Self::deposit_event(Event::LimitOrderPlaced {
+				order_id,
+				owner: sender,
+				asset_in,
+				asset_out,
+				amount_in,
+				min_amount_out,
+				expires_at,
+			});
+
+			Ok(())
+		}
+
+		//fills the limit orders found by a keeper, paying it the keeper fee of each of them.
+		//Orders that cannot be filled anymore are skipped
+		#[pallet::call_index(25)]
+		#[pallet::weight(weights::execute_limit_orders::<T>(payload.order_ids.len() as u32))]
+		pub fn execute_limit_orders(
+			origin: OriginFor<T>,
+			payload: KeeperPayloadOf<T>,
+			_signature: T::Signature,
+		) -> DispatchResult {
+			//only keepers submit this call, their signature is checked by validate_unsigned
+			ensure_none(origin)?;
+
+			let keeper = payload.public.into_account();
+			ensure!(<Keepers<T>>::get(&keeper), Error::<T>::NotKeeper);
+			for order_id in payload.order_ids {
+				//a failed fill, for example with too much slippage, leaves no trace
+				let _ = with_storage_layer(|| Self::fill_limit_order(order_id, &keeper));
+			}
+
+			Ok(())
+		}
+
+		//swaps amount_per_period of asset_in for asset_out every period blocks, starting at the
+		//next block, until budget is spent, the sender cannot pay a period anymore or MaxDcaSkips
+		//periods in a row are skipped. Only the DcaDeposit is reserved, every swap is made by the
+		//sender with the balance it holds at that time
+		#[pallet::call_index(26)]
+		#[pallet::weight(0)]
+		pub fn schedule_dca(
+			origin: OriginFor<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			amount_per_period: BalanceOf<T>,
+			budget: BalanceOf<T>,
+			period: BlockNumberFor<T>,
+			max_slippage: Permill,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the amounts are not zero
+			Self::ensure_amount_not_zero(&asset_in, amount_per_period)?;
+			Self::ensure_amount_not_zero(&asset_in, budget)?;
+			ensure!(!period.is_zero(), Error::<T>::InvalidPeriod);
+
+			//verify the pools of the swap exist
+			let path = Self::dca_path(&asset_in, &asset_out)?;
+			Self::spot_amount_out(&path, amount_per_period)?;
+
+			//reserve the deposit, which is given back when the schedule ends
+			let deposit = T::DcaDeposit::get();
+			T::Currency::reserve(&sender, deposit)?;
+
+			let dca_id = <NextDcaId<T>>::get();
+			let next_execution =
+				frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+			<DcaQueue<T>>::try_mutate(next_execution, |queue| {
+				queue
+					.try_push((sender.clone(), dca_id))
+					.map_err(|_| Error::<T>::TooManyDcaSchedules)
+			})?;
+
+			<DcaSchedules<T>>::insert(
+				&sender,
+				dca_id,
+				DcaSchedule {
+					asset_in: asset_in.clone(),
+					asset_out: asset_out.clone(),
+					amount_per_period,
+					budget,
+					period,
+					max_slippage,
+					next_execution,
+					skipped: 0,
+					deposit,
+				},
+			);
+			<NextDcaId<T>>::put(dca_id.saturating_add(1));
+
+			//deposit event
+			Self::deposit_event(Event::DcaScheduled {
+				dca_id,
+				owner: sender,
+				asset_in,
+				asset_out,
+				amount_per_period,
+				budget,
+				period,
+			});
+
+			Ok(())
+		}
+
+		//stops a dca schedule of the sender
+		#[pallet::call_index(27)]
+		#[pallet::weight(0)]
+		pub fn cancel_dca(origin: OriginFor<T>, dca_id: DcaId) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			let schedule =
+				<DcaSchedules<T>>::get(&sender, dca_id).ok_or(Error::<T>::DcaNotFound)?;
+			<DcaQueue<T>>::mutate_exists(schedule.next_execution, |queue| {
+				if let Some(schedules) = queue {
+					schedules.retain(|(owner, id)| *owner != sender || *id != dca_id);
+					if schedules.is_empty() {
+						*queue = None;
+					}
+				}
+			});
+			Self::terminate_dca(&sender, dca_id, &schedule, DcaEnd::Cancelled);
+
+			Ok(())
+		}
+
+		//cancels a limit order and gives its input back to its owner
+		#[pallet::call_index(24)]
+		#[pallet::weight(0)]
+		pub fn cancel_limit_order(origin: OriginFor<T>, order_id: OrderId) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			let order = <LimitOrders<T>>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(order.owner == sender, Error::<T>::NotOrderOwner);
+
+			Self::release_limit_order(&order)?;
+			Self::remove_limit_order(order_id, &order);
+
+			//deposit event
+			Self::deposit_event(Event::LimitOrderCancelled { order_id, owner: sender });
+
+			Ok(())
+		}
+
+		//first phase of a swap that cannot be front-run: reserves the CommitBond and saves the
+		//hash of the swap, see commitment_of. The swap is only known when it is revealed
+		#[pallet::call_index(28)]
+		#[pallet::weight(0)]
+		pub fn commit_swap(origin: OriginFor<T>, commitment: T::Hash) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			ensure!(
+				!<SwapCommits<T>>::contains_key(&sender, commitment),
+				Error::<T>::CommitmentExists
+			);
+
+			//the bond is slashed the first block after the window
+			let committed_at = frame_system::Pallet::<T>::block_number();
+			let slashed_at =
+				committed_at.saturating_add(T::RevealWindow::get()).saturating_add(One::one());
+			<CommitExpiries<T>>::try_mutate(slashed_at, |expiring| {
+				expiring
+					.try_push((sender.clone(), commitment))
+					.map_err(|_| Error::<T>::TooManyCommits)
+			})?;
+
+			let bond = T::CommitBond::get();
+			T::Currency::reserve(&sender, bond)?;
+			<SwapCommits<T>>::insert(&sender, commitment, SwapCommit { bond, committed_at });
+
+			//deposit event
+			Self::deposit_event(Event::SwapCommitted { who: sender, commitment, bond });
+
+			Ok(())
+		}
+
+		//second phase of a swap that cannot be front-run: gives the bond back and executes the
+		//committed swap along its path. Must be called after the block of the commitment and
+		//within RevealWindow blocks. If the swap fails, for example because the price moved
+		//too much, the commitment is still consumed and the bond given back
+		#[pallet::call_index(29)]
+		#[pallet::weight(0)]
+		pub fn reveal_swap(
+			origin: OriginFor<T>,
+			params: SwapParamsOf<T>,
+			salt: [u8; 32],
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			let commitment = Self::commitment_of(&sender, &params, &salt);
+			let commit =
+				<SwapCommits<T>>::get(&sender, commitment).ok_or(Error::<T>::CommitmentNotFound)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() > commit.committed_at,
+				Error::<T>::RevealTooEarly
+			);
+
+			<SwapCommits<T>>::remove(&sender, commitment);
+			let slashed_at = commit
+				.committed_at
+				.saturating_add(T::RevealWindow::get())
+				.saturating_add(One::one());
+			<CommitExpiries<T>>::mutate_exists(slashed_at, |expiring| {
+				if let Some(commits) = expiring {
+					commits.retain(|(who, hash)| *who != sender || *hash != commitment);
+					if commits.is_empty() {
+						*expiring = None;
+					}
+				}
+			});
+			T::Currency::unreserve(&sender, commit.bond);
+
+			//deposit event
+			Self::deposit_event(Event::SwapRevealed { who: sender.clone(), commitment });
+
+			//a failed swap leaves no trace besides its event
+			let swapped = with_storage_layer(|| {
+				let path =
+					BoundedVec::try_from(params.path).map_err(|_| Error::<T>::InvalidPath)?;
+				Self::swap_exact_in_along_path(
+					frame_system::RawOrigin::Signed(sender.clone()).into(),
+					path,
+					params.amount_in,
+					params.min_amount_out,
+					None,
+				)
+			});
+			if let Err(error) = swapped {
+				Self::deposit_event(Event::RevealedSwapFailed { who: sender, commitment, error });
+			}
+
+			Ok(())
+		}
+
+		//switches the batch mode of a pool. While it is on, the swaps of the pool are only
+		//accepted through submit_batch_swap and cleared together at the end of the block
+		#[pallet::call_index(30)]
+		#[pallet::weight(0)]
+		pub fn set_batch_mode(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			enabled: bool,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let pool = Self::get_pool(asset_a, asset_b)?;
+			let asset_a = pool.asset_a;
+			let asset_b = pool.asset_b;
+			if enabled {
+				<BatchPools<T>>::insert((asset_a.clone(), asset_b.clone()), true);
+			} else {
+				<BatchPools<T>>::remove((asset_a.clone(), asset_b.clone()));
+			}
+
+			//deposit event
+			Self::deposit_event(Event::BatchModeUpdated { asset_a, asset_b, enabled });
+
+			Ok(())
+		}
+
+		//sends a swap to a pool in batch mode. The input is held by the pallet until the end of
+		//the block, when every swap of the batch is executed at the same price, or refunded if
+		//it does not receive at least min_amount_out
+		#[pallet::call_index(31)]
+		#[pallet::weight(weights::submit_batch_swap::<T>())]
+		pub fn submit_batch_swap(
+			origin: OriginFor<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			min_amount_out: BalanceOf<T>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			//verify the input amount is not zero
+			Self::ensure_amount_not_zero(&asset_in, amount_in)?;
+
+			//verify the pool can be used and clears its swaps in batches
+			let pool = Self::get_pool(asset_in.clone(), asset_out.clone())?;
+			Self::ensure_pool_active(&pool)?;
+			let pool_id = (pool.asset_a, pool.asset_b);
+			ensure!(<BatchPools<T>>::get(&pool_id), Error::<T>::PoolNotInBatchMode);
+
+			<BatchSwaps<T>>::try_mutate(&pool_id, |swaps| {
+				swaps
+					.try_push(BatchSwap {
+						who: sender.clone(),
+						asset_in: asset_in.clone(),
+						amount_in,
+						min_amount_out,
+					})
+					.map_err(|_| Error::<T>::TooManyBatchSwaps)
+			})?;
+
+			//transfer the input from sender to pallet
+			Self::transfer_asset_kind(&asset_in, &sender, &Self::account_id(), amount_in, true)?;
+
+			//deposit event
+			Self::deposit_event(Event::BatchSwapSubmitted {
+				who: sender,
+				asset_in,
+				asset_out,
+				amount_in,
+				min_amount_out,
+			});
+
+			Ok(())
+		}
+
+		//burns the whole liquidity of the sender in a pool destroyed while it still had
+		//providers and pays its share of the reserves. The last provider to withdraw finishes the
+		//destruction of the pool
+		#[pallet::call_index(32)]
+		#[pallet::weight(weights::finish_pool_destruction::<T>(T::DestroyItemsLimit::get()))]
+		pub fn withdraw_retired_liquidity(
+			origin: OriginFor<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+		) -> DispatchResult {
+			//verify origin signature
+			let sender = ensure_signed(origin)?;
+
+			let mut pool = <RetiredPools<T>>::get(&liquidity_asset_id)
+				.ok_or(Error::<T>::RetiredPoolNotFound)?;
+			let liquidity_amount = T::Fungibles::balance(liquidity_asset_id.clone(), &sender);
+			ensure!(!liquidity_amount.is_zero(), Error::<T>::LiqAmountZero);
+
+			//the share of the reserves is calculated like in remove_liquidity
+			let (amount_a, amount_b) = Self::liquidity_value(&pool, liquidity_amount)?;
+			T::Fungibles::burn_from(liquidity_asset_id.clone(), &sender, liquidity_amount)?;
+
+			let pallet_account = Self::account_id();
+			for (asset, amount) in [(&pool.asset_a, amount_a), (&pool.asset_b, amount_b)] {
+				if !amount.is_zero() {
+					Self::transfer_asset_kind(asset, &pallet_account, &sender, amount, false)?;
+				}
+			}
+			pool.reserve_a =
+				pool.reserve_a.checked_sub(&amount_a).ok_or(Error::<T>::OperationOverflow)?;
+			pool.reserve_b =
+				pool.reserve_b.checked_sub(&amount_b).ok_or(Error::<T>::OperationOverflow)?;
+
+			//deposit event
+			Self::deposit_event(Event::RetiredLiquidityWithdrawn {
+				who: sender,
+				liquidity_asset_id: liquidity_asset_id.clone(),
+				liquidity_amount,
+				amount_a,
+				amount_b,
+			});
+
+			if Self::providers_liquidity(&pool).is_zero() {
+				<RetiredPools<T>>::remove(&liquidity_asset_id);
+				return Self::finish_pool_destruction(pool)
+			}
+			<RetiredPools<T>>::insert(&liquidity_asset_id, pool);
+
+			Ok(())
+		}
+
+		//destroys up to DestroyItemsLimit more accounts and approvals of the liquidity asset of a
+		//removed pool, then finishes its destruction once none are left. Anyone can call it
+		#[pallet::call_index(33)]
+		#[pallet::weight(weights::finish_pool_destruction::<T>(T::DestroyItemsLimit::get()))]
+		pub fn continue_pool_destruction(
+			origin: OriginFor<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+		) -> DispatchResult {
+			//verify origin signature
+			ensure_signed(origin)?;
+
+			let pool = <DestroyingPools<T>>::get(&liquidity_asset_id)
+				.ok_or(Error::<T>::DestructionNotPending)?;
+
+			Self::destroy_liquidity_asset(pool)
+		}
+
+		//adds an account to the keepers allowed to submit fillable limit orders or removes it
+		#[pallet::call_index(34)]
+		#[pallet::weight(0)]
+		pub fn set_keeper(
+			origin: OriginFor<T>,
+			who: AccountIdOf<T>,
+			allowed: bool,
+		) -> DispatchResult {
+			//only governance can choose the keepers
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			if allowed {
+				<Keepers<T>>::insert(&who, true);
+			} else {
+				<Keepers<T>>::remove(&who);
+			}
+
+			//deposit event
+			Self::deposit_event(Event::KeeperUpdated { who, allowed });
+
+			Ok(())
+		}
+	}
+
+	//oracle struct
+	#[derive(
+		Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default, MaxEncodedLen, TypeInfo,
+	)]
 	pub struct OraclePrice<AssetId, AssetBalance> {
 		pub asset_id: AssetId,
 		pub asset_amount: AssetBalance,
 		pub currency_amount: AssetBalance,
 	}
 
-	//helper pallet functions
-	impl<T: Config> Pallet<T> {
-		pub fn account_id() -> T::AccountId {
-			T::PalletId::get().into_account_truncating()
+	//helper pallet functions
+	impl<T: Config> Pallet<T> {
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		//fails if the current block is past the given deadline, no deadline means the operation
+		//never expires
+		pub fn ensure_deadline(deadline: Option<BlockNumberFor<T>>) -> DispatchResult {
+			if let Some(deadline) = deadline {
+				ensure!(
+					frame_system::Pallet::<T>::block_number() <= deadline,
+					Error::<T>::DeadlineExpired
+				);
+			}
+			Ok(())
+		}
+
+		//returns the deadline attached to a dex call, if any
+		pub fn call_deadline(call: &Call<T>) -> Option<BlockNumberFor<T>> {
+			match call {
+				Call::create_pool { deadline, .. } |
+				Call::add_liquidity { deadline, .. } |
+				Call::remove_liquidity { deadline, .. } |
+				Call::currency_to_asset { deadline, .. } |
+				Call::asset_to_currency { deadline, .. } |
+				Call::asset_to_asset { deadline, .. } |
+				Call::mint_asset { deadline, .. } |
+				Call::currency_to_exact_asset { deadline, .. } |
+				Call::asset_to_exact_currency { deadline, .. } |
+				Call::asset_to_exact_asset { deadline, .. } |
+				Call::swap_exact_in_along_path { deadline, .. } |
+				Call::swap_exact_out_along_path { deadline, .. } |
+				Call::flash_swap { deadline, .. } => *deadline,
+				_ => None,
+			}
+		}
+
+		//fails if the path does not go through any pool, has more assets than max_hops or
+		//goes through the same pool or asset twice. The amounts of every hop are priced on the
+		//reserves before the swap, so the only repetition allowed is a last asset closing a loop
+		//back to the first one
+		pub fn ensure_valid_path(path: &[AssetKindOf<T>]) -> DispatchResult {
+			ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+			ensure!(
+				path.len() as u32 <= <DexParameters<T>>::get().max_hops,
+				Error::<T>::InvalidPath
+			);
+
+			let last = path.len() - 1;
+			for (i, asset) in path.iter().enumerate() {
+				let repeated = path[i + 1..]
+					.iter()
+					.enumerate()
+					.any(|(j, other)| other == asset && !(i == 0 && i + 1 + j == last));
+				ensure!(!repeated, Error::<T>::InvalidPath);
+			}
+
+			let mut pool_ids = Vec::with_capacity(last);
+			for hop in path.windows(2) {
+				let pool_id = Self::pool_id(hop[0].clone(), hop[1].clone())?;
+				ensure!(!pool_ids.contains(&pool_id), Error::<T>::InvalidPath);
+				pool_ids.push(pool_id);
+			}
+
+			Ok(())
+		}
+
+		//returns the id of the pool of a pair, which is the same no matter the order in which both
+		//assets are given
+		pub fn pool_id(
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+		) -> Result<PoolIdOf<T>, Error<T>> {
+			ensure!(asset_a != asset_b, Error::<T>::IdenticalAssets);
+
+			if asset_a < asset_b {
+				Ok((asset_a, asset_b))
+			} else {
+				Ok((asset_b, asset_a))
+			}
+		}
+
+		pub fn get_pool(
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+		) -> Result<PoolOf<T>, Error<T>> {
+			<PoolsMap<T>>::get(Self::pool_id(asset_a, asset_b)?).ok_or(Error::<T>::PoolNotFound)
+		}
+
+		//returns the reserves of a pool as (reserve of asset_in, reserve of the other side)
+		pub fn get_reserves(
+			pool: &PoolOf<T>,
+			asset_in: &AssetKindOf<T>,
+		) -> (BalanceOf<T>, BalanceOf<T>) {
+			if *asset_in == pool.asset_a {
+				(pool.reserve_a, pool.reserve_b)
+			} else {
+				(pool.reserve_b, pool.reserve_a)
+			}
+		}
+
+		//fails if the amount is zero, with the error matching the kind of asset
+		pub fn ensure_amount_not_zero(
+			asset: &AssetKindOf<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			match asset {
+				AssetKind::Native => ensure!(!amount.is_zero(), Error::<T>::CurrencyAmountZero),
+				AssetKind::Asset(_) => ensure!(!amount.is_zero(), Error::<T>::AssetAmountZero),
+			}
+			Ok(())
+		}
+
+		//the native currency always exists, fungible assets must be created
+		pub fn ensure_asset_exists(asset: &AssetKindOf<T>) -> DispatchResult {
+			if let AssetKind::Asset(asset_id) = asset {
+				ensure!(T::Fungibles::asset_exists(asset_id.clone()), Error::<T>::AssetNotFound);
+			}
+			Ok(())
+		}
+
+		//the native currency can always be listed, fungible assets only when listings are not
+		//restricted or they are in the allow-list
+		pub fn ensure_asset_allowed(asset: &AssetKindOf<T>) -> DispatchResult {
+			if let AssetKind::Asset(asset_id) = asset {
+				ensure!(
+					!T::RestrictListings::get() || <AllowedAssets<T>>::get(asset_id),
+					Error::<T>::AssetNotAllowed
+				);
+			}
+			Ok(())
+		}
+
+		//fails if the pool is in batch mode. Its swaps only go through submit_batch_swap, so that
+		//their order within the block does not matter
+		pub fn ensure_pool_not_batched(pool: &PoolOf<T>) -> DispatchResult {
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+			ensure!(!<BatchPools<T>>::get(pool_id), Error::<T>::PoolInBatchMode);
+			Ok(())
+		}
+
+		//fails if the pool or the whole dex is paused
+		pub fn ensure_pool_active(pool: &PoolOf<T>) -> DispatchResult {
+			ensure!(!<DexPaused<T>>::get(), Error::<T>::DexPaused);
+			ensure!(pool.status == PoolStatus::Active, Error::<T>::PoolPaused);
+			Self::ensure_pool_unlocked(pool)
+		}
+
+		//the reserves of a pool cannot change while a flash swap lends them
+		pub fn ensure_pool_unlocked(pool: &PoolOf<T>) -> DispatchResult {
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+			ensure!(!<FlashSwapLocks<T>>::get(pool_id), Error::<T>::PoolLocked);
+			Ok(())
+		}
+
+		pub fn set_pool_status(
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			status: PoolStatus,
+		) -> DispatchResult {
+			let mut pool = Self::get_pool(asset_a, asset_b)?;
+			pool.status = status;
+
+			let asset_a = pool.asset_a.clone();
+			let asset_b = pool.asset_b.clone();
+			<PoolsMap<T>>::insert((asset_a.clone(), asset_b.clone()), pool);
+
+			//deposit event
+			Self::deposit_event(Event::PoolStatusUpdated { asset_a, asset_b, status });
+
+			Ok(())
+		}
+
+		//transfers either currency or a fungible asset
+		pub fn transfer_asset_kind(
+			asset: &AssetKindOf<T>,
+			source: &AccountIdOf<T>,
+			dest: &AccountIdOf<T>,
+			amount: BalanceOf<T>,
+			keep_alive: bool,
+		) -> DispatchResult {
+			match asset {
+				AssetKind::Native => {
+					let existence = if keep_alive {
+						ExistenceRequirement::KeepAlive
+					} else {
+						ExistenceRequirement::AllowDeath
+					};
+					T::Currency::transfer(source, dest, amount, existence)
+				},
+				AssetKind::Asset(asset_id) =>
+					T::Fungibles::transfer(asset_id.clone(), source, dest, amount, keep_alive)
+						.map(|_| ()),
+			}
+		}
+
+		pub fn create_asset_helper(asset_id: AssetIdOf<T>) -> DispatchResult {
+			//verify the asset exists
+			ensure!(
+				!(T::Fungibles::asset_exists(asset_id.clone())),
+				Error::<T>::AssetAlreadyExists
+			);
+			T::Fungibles::create(asset_id, Self::account_id(), false, <BalanceOf<T>>::one())?;
+			Ok(())
+		}
+
+		//liquidity of a pool held by its providers, the liquidity locked in the pallet account
+		//does not belong to any of them
+		pub fn providers_liquidity(pool: &PoolOf<T>) -> BalanceOf<T> {
+			T::Fungibles::total_issuance(pool.liquidity_asset_id.clone()).saturating_sub(
+				T::Fungibles::balance(pool.liquidity_asset_id.clone(), &Self::account_id()),
+			)
+		}
+
+		//starts destroying the liquidity asset of a removed pool and finishes the destruction of
+		//the pool if the asset can be destroyed at once
+		pub fn finish_pool_destruction(pool: PoolOf<T>) -> DispatchResult {
+			T::Fungibles::start_destroy(pool.liquidity_asset_id.clone(), None)?;
+			Self::destroy_liquidity_asset(pool)
+		}
+
+		//destroys up to DestroyItemsLimit accounts and approvals of the liquidity asset of a
+		//removed pool. Once none are left the asset is destroyed, its metadata deposit refunded
+		//and the reserves left in the pool sent to the dust recipient. Until then the pool waits
+		//in DestroyingPools and keeps its reserves
+		pub fn destroy_liquidity_asset(pool: PoolOf<T>) -> DispatchResult {
+			let liquidity_asset_id = pool.liquidity_asset_id.clone();
+
+			//the destruction may also have been finished through the assets pallet
+			if T::Fungibles::asset_exists(liquidity_asset_id.clone()) {
+				let limit = T::DestroyItemsLimit::get();
+				let accounts_removed =
+					T::Fungibles::destroy_accounts(liquidity_asset_id.clone(), limit)?;
+				let approvals_removed =
+					T::Fungibles::destroy_approvals(liquidity_asset_id.clone(), limit)?;
+				if accounts_removed >= limit || approvals_removed >= limit {
+					<DestroyingPools<T>>::insert(&liquidity_asset_id, &pool);
+					Self::deposit_event(Event::PoolDestructionPending {
+						asset_a: pool.asset_a,
+						asset_b: pool.asset_b,
+						liquidity_asset_id,
+					});
+					return Ok(())
+				}
+				T::Fungibles::finish_destroy(liquidity_asset_id.clone())?;
+			}
+			<DestroyingPools<T>>::remove(&liquidity_asset_id);
+
+			//the metadata deposit released to the pallet account goes back to the creator
+			let pallet_account = Self::account_id();
+			if let Some((creator, deposit)) = <MetadataDeposits<T>>::take(&liquidity_asset_id) {
+				T::Currency::transfer(
+					&pallet_account,
+					&creator,
+					deposit,
+					ExistenceRequirement::KeepAlive,
+				)?;
+			}
+
+			//sweep the reserves left in the pool
+			let dust_recipient = T::DustRecipient::get();
+			let reserves = [(&pool.asset_a, pool.reserve_a), (&pool.asset_b, pool.reserve_b)];
+			for (asset, dust) in reserves.into_iter().filter(|(_, dust)| !dust.is_zero()) {
+				Self::transfer_asset_kind(asset, &pallet_account, &dust_recipient, dust, false)?;
+			}
+
+			//deposit event
+			Self::deposit_event(Event::PoolDestroyed {
+				asset_a: pool.asset_a,
+				asset_b: pool.asset_b,
+				liquidity_asset_id,
+				dust_a: pool.reserve_a,
+				dust_b: pool.reserve_b,
+			});
+
+			Ok(())
+		}
+
+		//gives back the open limit orders selling either side of a pool for the other one
+		pub fn cancel_pool_limit_orders(pool_id: &PoolIdOf<T>) -> DispatchResult {
+			let (asset_a, asset_b) = pool_id.clone();
+			for book in [(asset_a.clone(), asset_b.clone()), (asset_b, asset_a)] {
+				for (_, order_id) in <OrderBooks<T>>::get(&book) {
+					if let Some(order) = <LimitOrders<T>>::get(order_id) {
+						Self::release_limit_order(&order)?;
+						Self::remove_limit_order(order_id, &order);
+						Self::deposit_event(Event::LimitOrderCancelled {
+							order_id,
+							owner: order.owner,
+						});
+					}
+				}
+			}
+			Ok(())
+		}
+
+		//creates the liquidity asset of a new pool with the next free id of the reserved range
+		pub fn create_liquidity_asset() -> Result<AssetIdOf<T>, DispatchError> {
+			//skip the ids already taken by other assets, up to MAX_LIQUIDITY_ASSET_ID_PROBES
+			let mut liquidity_asset_id = <NextLiquidityAssetId<T>>::get();
+			let mut probes = 0;
+			while T::Fungibles::asset_exists(liquidity_asset_id.clone()) {
+				probes += 1;
+				ensure!(
+					probes <= MAX_LIQUIDITY_ASSET_ID_PROBES,
+					Error::<T>::LiquidityAssetIdUnavailable
+				);
+				liquidity_asset_id = liquidity_asset_id
+					.checked_add(&One::one())
+					.ok_or(Error::<T>::OperationOverflow)?;
+			}
+
+			let next_liquidity_asset_id = liquidity_asset_id
+				.checked_add(&One::one())
+				.ok_or(Error::<T>::OperationOverflow)?;
+			<NextLiquidityAssetId<T>>::put(next_liquidity_asset_id);
+
+			Self::create_asset_helper(liquidity_asset_id.clone())?;
+
+			Ok(liquidity_asset_id)
+		}
+
+		//names the liquidity asset of a new pool after the symbols of both sides of the pool. The
+		//pallet account owns the liquidity asset, so the metadata deposit is reserved from it,
+		//and who pays it back. The deposit is refunded to who once the liquidity asset is
+		//destroyed
+		pub fn set_liquidity_asset_metadata(
+			who: &AccountIdOf<T>,
+			liquidity_asset_id: AssetIdOf<T>,
+			asset_a: &AssetKindOf<T>,
+			asset_b: &AssetKindOf<T>,
+		) -> DispatchResult {
+			//the currency has no symbol, so the liquidity asset of a currency pool is named only
+			//after the fungible asset, e.g. LP-DOT, while asset pools use both, e.g. LP-DOT-KSM
+			let pair = [asset_a, asset_b]
+				.iter()
+				.filter_map(|asset| match asset {
+					AssetKind::Native => None,
+					AssetKind::Asset(asset_id) =>
+						Some(<T::Fungibles as fungibles::InspectMetadata<T::AccountId>>::symbol(
+							asset_id,
+						)),
+				})
+				.map(|symbol| if symbol.is_empty() { UNKNOWN_SYMBOL.to_vec() } else { symbol })
+				.collect::<Vec<_>>()
+				.join(&b'-');
+
+			//long symbols are cut, the assets pallet rejects metadata over its StringLimit
+			let limit = T::StringLimit::get() as usize;
+			let mut name = [b"Dex liquidity ".to_vec(), pair.clone()].concat();
+			name.truncate(limit);
+			let mut symbol = [b"LP-".to_vec(), pair].concat();
+			symbol.truncate(limit);
+
+			//liquidity is minted in units of asset_a
+			let decimals = match asset_a {
+				AssetKind::Native => T::NativeDecimals::get(),
+				AssetKind::Asset(asset_id) =>
+					<T::Fungibles as fungibles::InspectMetadata<T::AccountId>>::decimals(asset_id),
+			};
+
+			let pallet_account = Self::account_id();
+			let reserved = T::Currency::reserved_balance(&pallet_account);
+			<T::Fungibles as fungibles::metadata::Mutate<T::AccountId>>::set(
+				liquidity_asset_id.clone(),
+				&pallet_account,
+				name,
+				symbol,
+				decimals,
+			)?;
+
+			//the pallet account holds the reserves, so the deposit taken from it is refilled
+			let deposit = T::Currency::reserved_balance(&pallet_account).saturating_sub(reserved);
+			if !deposit.is_zero() {
+				T::Currency::transfer(
+					who,
+					&pallet_account,
+					deposit,
+					ExistenceRequirement::KeepAlive,
+				)?;
+				<MetadataDeposits<T>>::insert(liquidity_asset_id, (who.clone(), deposit));
+			}
+
+			Ok(())
+		}
+
+		//amount_a and amount_b must be given in the same order as the sides of the pool
+		pub fn add_liquidity_helper(
+			mut pool: PoolOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+			liquidity_minted: BalanceOf<T>,
+			provider: AccountIdOf<T>,
+		) -> DispatchResult {
+			//new liquidity is not accepted while the pool is paused
+			Self::ensure_pool_active(&pool)?;
+
+			//accumulate the price that lasted until now, before the reserves change
+			Self::update_cumulative_prices(&mut pool);
+
+			let pallet_account = Self::account_id();
+
+			//transfer both amounts from liquidity provider account to pallet account
+			Self::transfer_asset_kind(&pool.asset_a, &provider, &pallet_account, amount_a, true)?;
+			Self::transfer_asset_kind(&pool.asset_b, &provider, &pallet_account, amount_b, true)?;
+
+			//mint liquidity assets to liquidity provider account
+			T::Fungibles::mint_into(pool.liquidity_asset_id.clone(), &provider, liquidity_minted)?;
+
+			//update pool's reserves
+			pool.reserve_a =
+				pool.reserve_a.checked_add(&amount_a).ok_or(Error::<T>::OperationOverflow)?;
+			pool.reserve_b =
+				pool.reserve_b.checked_add(&amount_b).ok_or(Error::<T>::OperationOverflow)?;
+
+			//update pool in storage
+			let asset_a = pool.asset_a.clone();
+			let asset_b = pool.asset_b.clone();
+			<PoolsMap<T>>::insert((asset_a.clone(), asset_b.clone()), pool);
+
+			Self::deposit_event(Event::LiquidityAdded {
+				provider,
+				asset_a,
+				asset_b,
+				amount_a,
+				amount_b,
+				liquidity_minted,
+			});
+
+			Ok(())
+		}
+
+		//amount_a and amount_b must be given in the same order as the sides of the pool
+		pub fn remove_liquidity_helper(
+			mut pool: PoolOf<T>,
+			amount_a: BalanceOf<T>,
+			amount_b: BalanceOf<T>,
+			liquidity_amount: BalanceOf<T>,
+			provider: AccountIdOf<T>,
+		) -> DispatchResult {
+			//paused pools can still be left, but not while a flash swap lends their reserves
+			Self::ensure_pool_unlocked(&pool)?;
+
+			//accumulate the price that lasted until now, before the reserves change
+			Self::update_cumulative_prices(&mut pool);
+
+			let pallet_account = Self::account_id();
+
+			//burn liquidity assets
+			T::Fungibles::burn_from(pool.liquidity_asset_id.clone(), &provider, liquidity_amount)?;
+
+			//transfer both amounts from pallet to provider
+			Self::transfer_asset_kind(&pool.asset_a, &pallet_account, &provider, amount_a, false)?;
+			Self::transfer_asset_kind(&pool.asset_b, &pallet_account, &provider, amount_b, false)?;
+
+			//update pool's reserves
+			pool.reserve_a =
+				pool.reserve_a.checked_sub(&amount_a).ok_or(Error::<T>::OperationOverflow)?;
+			pool.reserve_b =
+				pool.reserve_b.checked_sub(&amount_b).ok_or(Error::<T>::OperationOverflow)?;
+
+			//update pool in storage
+			let asset_a = pool.asset_a.clone();
+			let asset_b = pool.asset_b.clone();
+			<PoolsMap<T>>::insert((asset_a.clone(), asset_b.clone()), pool);
+
+			//deposit event
+			Self::deposit_event(Event::LiquidityRemoved {
+				provider,
+				asset_a,
+				asset_b,
+				amount_a,
+				amount_b,
+				liquidity_amount,
+			});
+
+			Ok(())
+		}
+
+		//adds amount_in, less the protocol fee, to the reserve of asset_in and takes amount_out
+		//from the other reserve
+		pub fn update_swap_reserves(
+			mut pool: PoolOf<T>,
+			asset_in: &AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+			amount_out: BalanceOf<T>,
+		) -> DispatchResult {
+			//every swap goes through here, so this is where paused pools are stopped
+			Self::ensure_pool_active(&pool)?;
+			Self::ensure_pool_not_batched(&pool)?;
+
+			//verify the swap does not move the price too much
+			let (reserve_in, _) = Self::get_reserves(&pool, asset_in);
+			Self::ensure_price_impact(amount_in, reserve_in)?;
+
+			//accumulate the price that lasted until now, before the reserves change
+			Self::update_cumulative_prices(&mut pool);
+
+			//send the protocol share of the swap fee to the recipient
+			let protocol_fee = Self::collect_protocol_fee(&pool, asset_in, amount_in);
+			let amount_in =
+				amount_in.checked_sub(&protocol_fee).ok_or(Error::<T>::OperationOverflow)?;
+
+			let (reserve_in, reserve_out) = if *asset_in == pool.asset_a {
+				(&mut pool.reserve_a, &mut pool.reserve_b)
+			} else {
+				(&mut pool.reserve_b, &mut pool.reserve_a)
+			};
+
+			*reserve_in =
+				reserve_in.checked_add(&amount_in).ok_or(Error::<T>::OperationOverflow)?;
+			*reserve_out =
+				reserve_out.checked_sub(&amount_out).ok_or(Error::<T>::OperationOverflow)?;
+
+			//update pool in storage
+			<PoolsMap<T>>::insert((pool.asset_a.clone(), pool.asset_b.clone()), pool);
+
+			Ok(())
+		}
+
+		//the currency of a limit order is reserved from its owner, and fungible assets, which
+		//cannot be reserved, are moved to the pallet account
+		pub fn escrow_limit_order(order: &LimitOrderOf<T>) -> DispatchResult {
+			match order.asset_in {
+				AssetKind::Native => T::Currency::reserve(&order.owner, order.amount_in),
+				AssetKind::Asset(_) => Self::transfer_asset_kind(
+					&order.asset_in,
+					&order.owner,
+					&Self::account_id(),
+					order.amount_in,
+					true,
+				),
+			}
+		}
+
+		//gives the escrowed input of a limit order back to its owner
+		pub fn release_limit_order(order: &LimitOrderOf<T>) -> DispatchResult {
+			Self::release_limit_order_to(order, &order.owner, order.amount_in)
+		}
+
+		//sends amount of the escrowed input of a limit order to who, the currency reserved from
+		//the owner is moved to the free balance of who
+		pub fn release_limit_order_to(
+			order: &LimitOrderOf<T>,
+			who: &AccountIdOf<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			match order.asset_in {
+				AssetKind::Native if *who == order.owner => {
+					T::Currency::unreserve(&order.owner, amount);
+					Ok(())
+				},
+				AssetKind::Native => {
+					let missing = T::Currency::repatriate_reserved(
+						&order.owner,
+						who,
+						amount,
+						BalanceStatus::Free,
+					)?;
+					ensure!(missing.is_zero(), Error::<T>::InsufficientCurrencyBalance);
+					Ok(())
+				},
+				AssetKind::Asset(_) => Self::transfer_asset_kind(
+					&order.asset_in,
+					&Self::account_id(),
+					who,
+					amount,
+					false,
+				),
+			}
+		}
+
+		//removes a limit order from storage, its order book and its expiry block
+		pub fn remove_limit_order(order_id: OrderId, order: &LimitOrderOf<T>) {
+			<LimitOrders<T>>::remove(order_id);
+			<OrderBooks<T>>::mutate_exists(
+				(order.asset_in.clone(), order.asset_out.clone()),
+				|book| {
+					if let Some(orders) = book {
+						orders.retain(|(_, id)| *id != order_id);
+						if orders.is_empty() {
+							*book = None;
+						}
+					}
+				},
+			);
+			<OrderExpiries<T>>::mutate_exists(order.expires_at, |expiring| {
+				if let Some(orders) = expiring {
+					orders.retain(|id| *id != order_id);
+					if orders.is_empty() {
+						*expiring = None;
+					}
+				}
+			});
+		}
+
+		//price of asset_in in units of asset_out, the limit price of orders selling asset_in
+		pub fn order_book_price(
+			asset_in: &AssetKindOf<T>,
+			asset_out: &AssetKindOf<T>,
+		) -> Option<FixedU128> {
+			let pool = Self::get_pool(asset_in.clone(), asset_out.clone()).ok()?;
+			let (reserve_in, reserve_out) = Self::get_reserves(&pool, asset_in);
+			FixedU128::checked_from_rational(reserve_out, reserve_in)
+		}
+
+		//output of a limit order at the current pool price once the keeper fee is paid
+		pub fn limit_order_amounts(
+			order: &LimitOrderOf<T>,
+		) -> Result<(BalanceOf<T>, Vec<BalanceOf<T>>), DispatchError> {
+			let keeper_fee = T::KeeperFee::get().mul_floor(order.amount_in);
+			let path = [order.asset_in.clone(), order.asset_out.clone()];
+			let amounts = Self::get_amounts_out(&path, order.amount_in.saturating_sub(keeper_fee))?;
+			Ok((keeper_fee, amounts))
+		}
+
+		//whether a limit order would get at least its minimum output if it was filled now
+		pub fn is_order_fillable(order: &LimitOrderOf<T>) -> bool {
+			Self::limit_order_amounts(order)
+				.ok()
+				.and_then(|(_, amounts)| amounts.last().cloned())
+				.map_or(false, |amount_out| amount_out >= order.min_amount_out)
+		}
+
+		//swaps the input of a limit order for its owner, less the keeper fee paid to the keeper.
+		//Fails if the output is lower than its minimum or the swap cannot take place
+		pub fn fill_limit_order(order_id: OrderId, keeper: &AccountIdOf<T>) -> DispatchResult {
+			let order = <LimitOrders<T>>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			let path = [order.asset_in.clone(), order.asset_out.clone()];
+
+			let (keeper_fee, amounts) = Self::limit_order_amounts(&order)?;
+			let amount_out = amounts.last().cloned().ok_or(Error::<T>::InvalidPath)?;
+			ensure!(amount_out >= order.min_amount_out, Error::<T>::SlippageExceeded);
+
+			//the keeper fee is paid out of the escrow, and the owner swaps the rest of its input
+			//once it leaves the escrow
+			if !keeper_fee.is_zero() {
+				Self::release_limit_order_to(&order, keeper, keeper_fee)?;
+			}
+			Self::release_limit_order_to(
+				&order,
+				&order.owner,
+				order.amount_in.saturating_sub(keeper_fee),
+			)?;
+			Self::swap_along_path(&order.owner, &path, &amounts)?;
+			Self::remove_limit_order(order_id, &order);
+
+			//deposit event
+			Self::deposit_event(Event::LimitOrderExecuted {
+				order_id,
+				owner: order.owner,
+				keeper: keeper.clone(),
+				amount_in: order.amount_in,
+				amount_out,
+				keeper_fee,
+			});
+
+			Ok(())
+		}
+
+		//ids of the limit orders that can be filled at the current pool prices, going through
+		//every order book from the lowest limit price, at most MaxOrdersPerBook of them
+		pub fn fillable_orders() -> Vec<OrderId> {
+			<OrderBooks<T>>::iter()
+				.flat_map(|((asset_in, asset_out), book)| {
+					//the orders are sorted, so none after the first one above the pool price can
+					//be filled either
+					let price = Self::order_book_price(&asset_in, &asset_out);
+					book.into_iter()
+						.take_while(move |(limit_price, _)| {
+							price.map_or(false, |price| price >= *limit_price)
+						})
+						.map(|(_, order_id)| order_id)
+				})
+				.filter(|order_id| {
+					<LimitOrders<T>>::get(order_id)
+						.map_or(false, |order| Self::is_order_fillable(&order))
+				})
+				.take(T::MaxOrdersPerBook::get() as usize)
+				.collect()
+		}
+
+		//runs in the offchain worker of every keeper, reading the pools and orders of the block
+		//state, and submits the fillable orders in an unsigned transaction signed by the keeper
+		pub fn submit_fillable_orders(n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			let order_ids = Self::fillable_orders();
+			if order_ids.is_empty() {
+				return Ok(())
+			}
+
+			let (_, result) = Signer::<T, T::KeeperAuthorityId>::any_account()
+				.send_unsigned_transaction(
+					|account| KeeperPayload {
+						block_number: n,
+						order_ids: order_ids.clone(),
+						public: account.public.clone(),
+					},
+					|payload, signature| Call::execute_limit_orders { payload, signature },
+				)
+				.ok_or("no keeper key in the keystore")?;
+			result.map_err(|()| "unable to submit the keeper transaction")
+		}
+
+		//path swapped by a dca schedule, through the currency when there is no direct pool
+		//between two fungible assets
+		pub fn dca_path(
+			asset_in: &AssetKindOf<T>,
+			asset_out: &AssetKindOf<T>,
+		) -> Result<Vec<AssetKindOf<T>>, Error<T>> {
+			match (asset_in, asset_out) {
+				(AssetKind::Asset(asset_id_from), AssetKind::Asset(asset_id_to)) =>
+					Self::asset_to_asset_path(asset_id_from.clone(), asset_id_to.clone()),
+				(AssetKind::Native, AssetKind::Native) => Err(Error::<T>::InvalidPath),
+				_ => Ok(vec![asset_in.clone(), asset_out.clone()]),
+			}
+		}
+
+		//value of amount_in at the prices of the pools of the path, without fees nor price impact
+		pub fn spot_amount_out(
+			path: &[AssetKindOf<T>],
+			amount_in: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			path.windows(2).try_fold(amount_in, |amount, hop| {
+				let pool = Self::get_pool(hop[0].clone(), hop[1].clone())?;
+				let (reserve_in, reserve_out) = Self::get_reserves(&pool, &hop[0]);
+				Self::multiply_by_rational(amount, reserve_out, reserve_in, Rounding::Down)
+			})
+		}
+
+		//balance of either currency or a fungible asset
+		pub fn balance_of(asset: &AssetKindOf<T>, who: &AccountIdOf<T>) -> BalanceOf<T> {
+			match asset {
+				AssetKind::Native => T::Currency::free_balance(who),
+				AssetKind::Asset(asset_id) => T::Fungibles::balance(asset_id.clone(), who),
+			}
+		}
+
+		//balance that can be swapped without killing the account
+		pub fn spendable_balance(asset: &AssetKindOf<T>, who: &AccountIdOf<T>) -> BalanceOf<T> {
+			match asset {
+				AssetKind::Native =>
+					T::Currency::free_balance(who).saturating_sub(T::Currency::minimum_balance()),
+				AssetKind::Asset(asset_id) =>
+					T::Fungibles::reducible_balance(asset_id.clone(), who, true),
+			}
 		}
 
-		pub fn create_asset_helper(asset_id: AssetIdOf<T>) -> DispatchResult {
-			//verify the asset exists
-			ensure!(
-				!(T::Fungibles::asset_exists(asset_id.clone())),
-				Error::<T>::AssetAlreadyExists
-			);
-			T::Fungibles::create(asset_id, Self::account_id(), false, <BalanceOf<T>>::one())?;
-			Ok(())
+		//swaps amount_in of a dca schedule with the swap extrinsic of its pair, as if the owner
+		//had called it, and returns the amount received
+		pub fn swap_dca(
+			owner: &AccountIdOf<T>,
+			schedule: &DcaScheduleOf<T>,
+			amount_in: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let path = Self::dca_path(&schedule.asset_in, &schedule.asset_out)?;
+			let min_amount_out = (Permill::one() - schedule.max_slippage)
+				.mul_floor(Self::spot_amount_out(&path, amount_in)?);
+
+			let balance_before = Self::balance_of(&schedule.asset_out, owner);
+			let origin: OriginFor<T> = frame_system::RawOrigin::Signed(owner.clone()).into();
+			match (&schedule.asset_in, &schedule.asset_out) {
+				(AssetKind::Native, AssetKind::Asset(asset_id)) => Self::currency_to_asset(
+					origin,
+					amount_in,
+					asset_id.clone(),
+					min_amount_out,
+					None,
+				),
+				(AssetKind::Asset(asset_id), AssetKind::Native) => Self::asset_to_currency(
+					origin,
+					amount_in,
+					asset_id.clone(),
+					min_amount_out,
+					None,
+				),
+				(AssetKind::Asset(asset_id_from), AssetKind::Asset(asset_id_to)) =>
+					Self::asset_to_asset(
+						origin,
+						asset_id_from.clone(),
+						asset_id_to.clone(),
+						amount_in,
+						min_amount_out,
+						None,
+					),
+				_ => Err(Error::<T>::InvalidPath.into()),
+			}?;
+
+			Ok(Self::balance_of(&schedule.asset_out, owner).saturating_sub(balance_before))
 		}
 
-		pub fn add_liquidity_helper(
-			mut pool: PoolOf<T>,
-			currency_amount: BalanceOf<T>,
-			asset_amount: BalanceOf<T>,
-			liquidity_minted: BalanceOf<T>,
-			provider: AccountIdOf<T>,
-		) -> DispatchResult {
-			let asset_id = pool.asset_id.clone();
-			let pallet_account = Self::account_id();
+		//removes a dca schedule that will not run anymore and gives its deposit back
+		pub fn terminate_dca(
+			owner: &AccountIdOf<T>,
+			dca_id: DcaId,
+			schedule: &DcaScheduleOf<T>,
+			reason: DcaEnd,
+		) {
+			<DcaSchedules<T>>::remove(owner, dca_id);
+			T::Currency::unreserve(owner, schedule.deposit);
+			Self::deposit_event(Event::DcaTerminated { dca_id, owner: owner.clone(), reason });
+		}
 
-			//transfer the respective currency amount from liquidity provider account to pallet
-			// account
-			T::Currency::transfer(
-				&provider,
-				&pallet_account,
-				currency_amount,
-				ExistenceRequirement::KeepAlive,
-			)?;
+		//runs a period of a dca schedule and queues the next one
+		pub fn execute_dca(
+			owner: &AccountIdOf<T>,
+			dca_id: DcaId,
+			mut schedule: DcaScheduleOf<T>,
+			n: BlockNumberFor<T>,
+		) {
+			//a pool of the swap was destroyed, so the schedule cannot run anymore
+			let pools_exist =
+				Self::dca_path(&schedule.asset_in, &schedule.asset_out).map_or(false, |path| {
+					path.windows(2)
+						.all(|hop| Self::get_pool(hop[0].clone(), hop[1].clone()).is_ok())
+				});
+			if !pools_exist {
+				return Self::terminate_dca(owner, dca_id, &schedule, DcaEnd::PoolDestroyed)
+			}
 
-			//transfer the respective asset amount from liquidity provider account to pallet
-			// account
-			T::Fungibles::transfer(
-				asset_id.clone(),
-				&provider,
-				&pallet_account,
-				asset_amount,
+			//the last period spends what is left of the budget
+			let amount_in = schedule.amount_per_period.min(schedule.budget);
+			if Self::spendable_balance(&schedule.asset_in, owner) < amount_in {
+				return Self::terminate_dca(owner, dca_id, &schedule, DcaEnd::InsufficientBalance)
+			}
+
+			//a failed swap, for example with too much slippage, leaves no trace and is not
+			//charged to the budget, but a schedule that keeps failing ends
+			match with_storage_layer(|| Self::swap_dca(owner, &schedule, amount_in)) {
+				Ok(amount_out) => {
+					schedule.budget = schedule.budget.saturating_sub(amount_in);
+					schedule.skipped = 0;
+					Self::deposit_event(Event::DcaExecuted {
+						dca_id,
+						owner: owner.clone(),
+						amount_in,
+						amount_out,
+					});
+				},
+				Err(error) => {
+					schedule.skipped = schedule.skipped.saturating_add(1);
+					Self::deposit_event(Event::DcaSkipped { dca_id, owner: owner.clone(), error });
+				},
+			}
+			if schedule.budget.is_zero() {
+				return Self::terminate_dca(owner, dca_id, &schedule, DcaEnd::BudgetSpent)
+			}
+			if schedule.skipped >= T::MaxDcaSkips::get() {
+				return Self::terminate_dca(owner, dca_id, &schedule, DcaEnd::TooManySkips)
+			}
+
+			schedule.next_execution = n.saturating_add(schedule.period);
+			let queued = <DcaQueue<T>>::try_mutate(schedule.next_execution, |queue| {
+				queue.try_push((owner.clone(), dca_id))
+			});
+			match queued {
+				Ok(()) => <DcaSchedules<T>>::insert(owner, dca_id, schedule),
+				Err(_) => Self::terminate_dca(owner, dca_id, &schedule, DcaEnd::QueueFull),
+			}
+		}
+
+		//weight of clearing the batches in on_finalize. The swaps are submitted after
+		//on_initialize, so every pool in batch mode is counted with MaxBatchSwaps swaps
+		pub fn batch_clearing_weight() -> Weight {
+			let pools = <BatchPools<T>>::iter_keys().count() as u32;
+			weights::clear_batches::<T>(pools, T::MaxBatchSwaps::get())
+		}
+
+		//executes the dca schedules due at the given block
+		pub fn execute_dca_schedules(n: BlockNumberFor<T>) -> Weight {
+			let due = <DcaQueue<T>>::take(n);
+			for (owner, dca_id) in due.iter() {
+				if let Some(schedule) = <DcaSchedules<T>>::get(owner, dca_id) {
+					Self::execute_dca(owner, *dca_id, schedule, n);
+				}
+			}
+
+			//rough cost of a swap, the pallet has no benchmarks yet
+			let schedules = due.len() as u64;
+			T::DbWeight::get().reads_writes(1 + 12 * schedules, 1 + 10 * schedules)
+		}
+
+		//outputs of the swaps of a batch, and the price of asset_a they are all executed at. The
+		//inputs less the fee are added to the reserves first, and every swap receives its input
+		//less the fee at the ratio of those reserves, rounded down. This keeps the constant
+		//product of the pool whatever the amounts are on each side, and does not depend on the
+		//order of the swaps
+		pub fn batch_outputs(
+			pool: &PoolOf<T>,
+			swaps: &[BatchSwapOf<T>],
+		) -> Result<(Vec<BalanceOf<T>>, FixedU128), Error<T>> {
+			let after_fee =
+				|amount: BalanceOf<T>| amount.saturating_sub(pool.fee.mul_floor(amount));
+
+			let (mut depth_a, mut depth_b) = (pool.reserve_a, pool.reserve_b);
+			for swap in swaps {
+				let depth = if swap.asset_in == pool.asset_a { &mut depth_a } else { &mut depth_b };
+				*depth = depth
+					.checked_add(&after_fee(swap.amount_in))
+					.ok_or(Error::<T>::OperationOverflow)?;
+			}
+
+			let outputs = swaps
+				.iter()
+				.map(|swap| {
+					let (depth_in, depth_out) = if swap.asset_in == pool.asset_a {
+						(depth_a, depth_b)
+					} else {
+						(depth_b, depth_a)
+					};
+					Self::multiply_by_rational(
+						after_fee(swap.amount_in),
+						depth_out,
+						depth_in,
+						Rounding::Down,
+					)
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			let price_a = FixedU128::checked_from_rational(depth_b, depth_a)
+				.ok_or(Error::<T>::InsufficientPoolReserve)?;
+
+			Ok((outputs, price_a))
+		}
+
+		//gives the input of a batch swap back, keeping the pallet account alive
+		pub fn refund_batch_swap(swap: BatchSwapOf<T>) {
+			let refunded = Self::transfer_asset_kind(
+				&swap.asset_in,
+				&Self::account_id(),
+				&swap.who,
+				swap.amount_in,
 				true,
-			)?;
+			);
+			match refunded {
+				Ok(()) => Self::deposit_event(Event::BatchSwapRefunded {
+					who: swap.who,
+					asset_in: swap.asset_in,
+					amount_in: swap.amount_in,
+				}),
+				Err(error) => Self::deposit_event(Event::BatchSwapRefundFailed {
+					who: swap.who,
+					asset_in: swap.asset_in,
+					amount_in: swap.amount_in,
+					error,
+				}),
+			}
+		}
 
-			//mint liquidity assets to liquidity provider account
-			T::Fungibles::mint_into(pool.liquidity_asset_id.clone(), &provider, liquidity_minted)?;
+		//pays the outputs of a batch and updates the reserves of its pool
+		pub fn settle_batch(
+			mut pool: PoolOf<T>,
+			swaps: &[BatchSwapOf<T>],
+			outputs: &[BalanceOf<T>],
+			price_a: FixedU128,
+		) -> DispatchResult {
+			//the batch moves the price like a swap of the whole input of each side would
+			let zero = <BalanceOf<T>>::zero();
+			let input_of = |asset: &AssetKindOf<T>| {
+				swaps
+					.iter()
+					.filter(|swap| swap.asset_in == *asset)
+					.fold(zero, |total, swap| total.saturating_add(swap.amount_in))
+			};
+			Self::ensure_price_impact(input_of(&pool.asset_a), pool.reserve_a)?;
+			Self::ensure_price_impact(input_of(&pool.asset_b), pool.reserve_b)?;
+
+			//accumulate the price that lasted until now, before the reserves change
+			Self::update_cumulative_prices(&mut pool);
+
+			let (mut amount_a_in, mut amount_b_in) = (zero, zero);
+			let (mut amount_a_out, mut amount_b_out) = (zero, zero);
+			for (swap, amount_out) in swaps.iter().zip(outputs) {
+				let (amount_in, amount_out_total, asset_out) = if swap.asset_in == pool.asset_a {
+					(&mut amount_a_in, &mut amount_b_out, &pool.asset_b)
+				} else {
+					(&mut amount_b_in, &mut amount_a_out, &pool.asset_a)
+				};
+				*amount_in = amount_in.saturating_add(swap.amount_in);
+				*amount_out_total = amount_out_total.saturating_add(*amount_out);
+
+				//transfer the output from pallet to the swapper
+				Self::transfer_asset_kind(
+					asset_out,
+					&Self::account_id(),
+					&swap.who,
+					*amount_out,
+					false,
+				)?;
+				Self::deposit_event(Event::BatchSwapExecuted {
+					who: swap.who.clone(),
+					asset_in: swap.asset_in.clone(),
+					amount_in: swap.amount_in,
+					amount_out: *amount_out,
+				});
+			}
 
-			//update pool's reserves
-			pool.currency_reserve = pool
-				.currency_reserve
-				.checked_add(&currency_amount)
+			//send the protocol share of the swap fees to the recipient
+			let protocol_fee_a = Self::collect_protocol_fee(&pool, &pool.asset_a, amount_a_in);
+			let protocol_fee_b = Self::collect_protocol_fee(&pool, &pool.asset_b, amount_b_in);
+
+			pool.reserve_a = pool
+				.reserve_a
+				.checked_add(&amount_a_in.saturating_sub(protocol_fee_a))
+				.and_then(|reserve| reserve.checked_sub(&amount_a_out))
 				.ok_or(Error::<T>::OperationOverflow)?;
-			pool.asset_reserve = pool
-				.asset_reserve
-				.checked_add(&asset_amount)
+			pool.reserve_b = pool
+				.reserve_b
+				.checked_add(&amount_b_in.saturating_sub(protocol_fee_b))
+				.and_then(|reserve| reserve.checked_sub(&amount_b_out))
 				.ok_or(Error::<T>::OperationOverflow)?;
 
-			//update pool in storage
-			<PoolsMap<T>>::insert(asset_id.clone(), pool);
+			let asset_a = pool.asset_a.clone();
+			let asset_b = pool.asset_b.clone();
+			<PoolsMap<T>>::insert((asset_a.clone(), asset_b.clone()), pool);
 
-			Self::deposit_event(Event::LiquidityAdded {
-				provider,
-				asset_id,
-				currency_amount,
-				asset_amount,
-				liquidity_minted,
+			//deposit event
+			Self::deposit_event(Event::BatchCleared {
+				asset_a,
+				asset_b,
+				price_a,
+				amount_a_in,
+				amount_b_in,
+				amount_a_out,
+				amount_b_out,
 			});
 
 			Ok(())
 		}
 
-		pub fn remove_liquidity_helper(
-			mut pool: PoolOf<T>,
-			currency_amount: BalanceOf<T>,
-			asset_amount: BalanceOf<T>,
-			liquidity_amount: BalanceOf<T>,
-			provider: AccountIdOf<T>,
+		//clears the swaps submitted to a pool in batch mode during the block. Swaps that do not
+		//receive their minimum are refunded, which moves the price of the others, so the
+		//outputs are calculated again until every remaining swap gets its minimum
+		pub fn clear_batch(pool_id: PoolIdOf<T>, mut swaps: Vec<BatchSwapOf<T>>) {
+			let pool = match <PoolsMap<T>>::get(&pool_id) {
+				Some(pool) if Self::ensure_pool_active(&pool).is_ok() => pool,
+				_ => return swaps.into_iter().for_each(Self::refund_batch_swap),
+			};
+
+			while !swaps.is_empty() {
+				let (outputs, price_a) = match Self::batch_outputs(&pool, &swaps) {
+					Ok(cleared) => cleared,
+					Err(_) => break,
+				};
+
+				let (met, unmet): (Vec<_>, Vec<_>) =
+					swaps.into_iter().zip(outputs).partition(|(swap, amount_out)| {
+						!amount_out.is_zero() && *amount_out >= swap.min_amount_out
+					});
+				let (met_swaps, outputs): (Vec<_>, Vec<_>) = met.into_iter().unzip();
+				swaps = met_swaps;
+				if !unmet.is_empty() {
+					unmet.into_iter().for_each(|(swap, _)| Self::refund_batch_swap(swap));
+					continue
+				}
+
+				//a failed settlement leaves the pool untouched and refunds the whole batch
+				let settled = with_storage_layer(|| {
+					Self::settle_batch(pool.clone(), &swaps, &outputs, price_a)
+				});
+				if settled.is_ok() {
+					return
+				}
+				break
+			}
+
+			swaps.into_iter().for_each(Self::refund_batch_swap);
+		}
+
+		//commitment hiding a swap of who, the hash of (who, params, salt)
+		pub fn commitment_of(
+			who: &AccountIdOf<T>,
+			params: &SwapParamsOf<T>,
+			salt: &[u8; 32],
+		) -> T::Hash {
+			T::Hashing::hash_of(&(who, params, salt))
+		}
+
+		//slashes the bonds of the commitments whose window ended at the previous block
+		pub fn slash_unrevealed_commits(n: BlockNumberFor<T>) -> Weight {
+			let expiring = <CommitExpiries<T>>::take(n);
+			for (who, commitment) in expiring.iter() {
+				if let Some(commit) = <SwapCommits<T>>::take(who, commitment) {
+					let (_, not_slashed) = T::Currency::slash_reserved(who, commit.bond);
+					Self::deposit_event(Event::SwapCommitSlashed {
+						who: who.clone(),
+						commitment: *commitment,
+						amount: commit.bond.saturating_sub(not_slashed),
+					});
+				}
+			}
+
+			let commits = expiring.len() as u64;
+			T::DbWeight::get().reads_writes(1 + 2 * commits, 1 + 2 * commits)
+		}
+
+		//refunds the limit orders expiring at the given block
+		pub fn expire_limit_orders(n: BlockNumberFor<T>) -> Weight {
+			let expiring = <OrderExpiries<T>>::take(n);
+			for order_id in expiring.iter() {
+				if let Some(order) = <LimitOrders<T>>::get(order_id) {
+					//the order is only removed once its input is given back
+					match Self::release_limit_order(&order) {
+						Ok(()) => {
+							Self::remove_limit_order(*order_id, &order);
+							Self::deposit_event(Event::LimitOrderExpired {
+								order_id: *order_id,
+								owner: order.owner,
+							});
+						},
+						Err(error) => Self::deposit_event(Event::LimitOrderExpiryFailed {
+							order_id: *order_id,
+							owner: order.owner,
+							error,
+						}),
+					}
+				}
+			}
+
+			let orders = expiring.len() as u64;
+			T::DbWeight::get().reads_writes(1 + 3 * orders, 1 + 4 * orders)
+		}
+
+		//fails if the new reserves of a pool, with the fee charged on the amounts paid in, have a
+		//lower constant product than the current ones:
+		//(x' * One - in_x * Fee) * (y' * One - in_y * Fee) >= x * y * One^2
+		pub fn ensure_constant_product(
+			pool: &PoolOf<T>,
+			reserve_a: BalanceOf<T>,
+			reserve_b: BalanceOf<T>,
+			amount_a_in: BalanceOf<T>,
+			amount_b_in: BalanceOf<T>,
 		) -> DispatchResult {
-			let asset_id = pool.asset_id.clone();
-			let pallet_account = Self::account_id();
+			let one = <BalanceOf<T>>::from(Permill::ACCURACY);
+			let fee = <BalanceOf<T>>::from(pool.fee.deconstruct());
+
+			//x' * One - in_x * Fee, the fee is never charged on more than the new reserve
+			let adjusted = |reserve: BalanceOf<T>, amount_in: BalanceOf<T>| {
+				reserve
+					.checked_mul(&one)
+					.and_then(|reserve| reserve.checked_sub(&amount_in.checked_mul(&fee)?))
+					.ok_or(Error::<T>::OperationOverflow)
+			};
+			let adjusted_a = adjusted(reserve_a, amount_a_in)?;
+			let adjusted_b = adjusted(reserve_b, amount_b_in)?;
 
-			//burn liquidity assets
-			T::Fungibles::burn_from(pool.liquidity_asset_id.clone(), &provider, liquidity_amount)?;
+			//x * One and y * One
+			let scaled_a = pool.reserve_a.checked_mul(&one).ok_or(Error::<T>::OperationOverflow)?;
+			let scaled_b = pool.reserve_b.checked_mul(&one).ok_or(Error::<T>::OperationOverflow)?;
 
-			//transfer currency from pallet to provider
-			<T as Config>::Currency::transfer(
-				&pallet_account,
-				&provider,
-				currency_amount,
-				ExistenceRequirement::AllowDeath,
-			)?;
+			//y' * One - in_y * Fee >= x * y * One^2 / (x' * One - in_x * Fee), rounded up. A
+			//quotient that does not fit in a balance cannot be reached either
+			let holds = Self::multiply_by_rational(scaled_a, scaled_b, adjusted_a, Rounding::Up)
+				.map_or(false, |required_b| adjusted_b >= required_b);
+			ensure!(holds, Error::<T>::InvariantViolated);
 
-			//transfer asset from pallet to provider
-			T::Fungibles::transfer(
-				asset_id.clone(),
-				&pallet_account,
-				&provider,
-				asset_amount,
-				false,
-			)?;
+			Ok(())
+		}
 
-			//update pool's reserves
-			pool.currency_reserve = pool
-				.currency_reserve
-				.checked_sub(&currency_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
-			pool.asset_reserve = pool
-				.asset_reserve
-				.checked_sub(&asset_amount)
-				.ok_or(Error::<T>::OperationOverflow)?;
+		//fails if adding amount_in to reserve_in moves the price more than max_price_impact, the
+		//price impact of a swap is amount_in / (reserve_in + amount_in)
+		pub fn ensure_price_impact(
+			amount_in: BalanceOf<T>,
+			reserve_in: BalanceOf<T>,
+		) -> DispatchResult {
+			let amount_in = amount_in.saturated_into::<u128>();
+			let price_impact = Permill::from_rational(
+				amount_in,
+				reserve_in.saturated_into::<u128>().saturating_add(amount_in),
+			);
 
-			//update pool in storage
-			<PoolsMap<T>>::insert(asset_id.clone(), pool);
+			ensure!(
+				price_impact <= <DexParameters<T>>::get().max_price_impact,
+				Error::<T>::PriceImpactTooHigh
+			);
+			Ok(())
+		}
 
-			//deposit event
-			Self::deposit_event(Event::LiquidityRemoved {
-				provider,
-				asset_id,
-				currency_amount,
-				asset_amount,
-				liquidity_amount,
+		//transfers the protocol share of the fee paid on amount_in to the protocol fee recipient
+		//and returns the amount transferred, which is zero while the protocol fee is switched off.
+		//It never fails the swap: a fee the recipient cannot receive, for example below the
+		//existential deposit of an account that does not exist yet, stays in the pool
+		pub fn collect_protocol_fee(
+			pool: &PoolOf<T>,
+			asset_in: &AssetKindOf<T>,
+			amount_in: BalanceOf<T>,
+		) -> BalanceOf<T> {
+			//protocol_fee = share * fee * amount_in, rounded down
+			let protocol_fee = <DexParameters<T>>::get()
+				.protocol_fee_share
+				.mul_floor(pool.fee.mul_floor(amount_in));
+			if protocol_fee.is_zero() {
+				return protocol_fee
+			}
+
+			let transferred = with_storage_layer(|| {
+				Self::transfer_asset_kind(
+					asset_in,
+					&Self::account_id(),
+					&T::ProtocolFeeRecipient::get(),
+					protocol_fee,
+					true,
+				)
+			});
+			if transferred.is_err() {
+				return Zero::zero()
+			}
+
+			//keep track of the fees collected by the pool
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+			<ProtocolFeesCollected<T>>::mutate(&pool_id, |collected| {
+				let fees =
+					if *asset_in == pool.asset_a { &mut collected.0 } else { &mut collected.1 };
+				*fees = fees.saturating_add(protocol_fee);
+			});
+
+			Self::deposit_event(Event::ProtocolFeeCollected {
+				asset_a: pool_id.0,
+				asset_b: pool_id.1,
+				asset: asset_in.clone(),
+				amount: protocol_fee,
 			});
 
+			protocol_fee
+		}
+
+		//returns the route to swap between two assets: the direct pool if it exists, otherwise
+		//through the currency pools of both assets
+		pub fn asset_to_asset_path(
+			asset_id_from: AssetIdOf<T>,
+			asset_id_to: AssetIdOf<T>,
+		) -> Result<Vec<AssetKindOf<T>>, Error<T>> {
+			let asset_from = AssetKind::Asset(asset_id_from);
+			let asset_to = AssetKind::Asset(asset_id_to);
+
+			if <PoolsMap<T>>::contains_key(Self::pool_id(asset_from.clone(), asset_to.clone())?) {
+				Ok(vec![asset_from, asset_to])
+			} else {
+				Ok(vec![asset_from, AssetKind::Native, asset_to])
+			}
+		}
+
+		//calculates the amount received at every step of a path when swapping an exact input,
+		//the first amount is the input and the last one is the final output
+		pub fn get_amounts_out(
+			path: &[AssetKindOf<T>],
+			amount_in: BalanceOf<T>,
+		) -> Result<Vec<BalanceOf<T>>, Error<T>> {
+			let mut amounts = vec![amount_in];
+			let mut amount = amount_in;
+
+			for hop in path.windows(2) {
+				let pool = Self::get_pool(hop[0].clone(), hop[1].clone())?;
+				let (reserve_in, reserve_out) = Self::get_reserves(&pool, &hop[0]);
+				amount = Self::get_input_convert(amount, reserve_in, reserve_out, pool.fee)?;
+				amounts.push(amount);
+			}
+
+			Ok(amounts)
+		}
+
+		//calculates the amount needed at every step of a path to receive an exact output, the
+		//first amount is the input and the last one is the final output
+		pub fn get_amounts_in(
+			path: &[AssetKindOf<T>],
+			amount_out: BalanceOf<T>,
+		) -> Result<Vec<BalanceOf<T>>, Error<T>> {
+			let mut amounts = vec![amount_out];
+			let mut amount = amount_out;
+
+			for hop in path.windows(2).rev() {
+				let pool = Self::get_pool(hop[0].clone(), hop[1].clone())?;
+				let (reserve_in, reserve_out) = Self::get_reserves(&pool, &hop[0]);
+				amount = Self::get_output_convert(amount, reserve_in, reserve_out, pool.fee)?;
+				amounts.push(amount);
+			}
+
+			amounts.reverse();
+			Ok(amounts)
+		}
+
+		//updates the reserves of every pool of a path with the amounts swapped in each of them
+		pub fn update_path_reserves(
+			path: &[AssetKindOf<T>],
+			amounts: &[BalanceOf<T>],
+		) -> DispatchResult {
+			for (hop, amount) in path.windows(2).zip(amounts.windows(2)) {
+				let pool = Self::get_pool(hop[0].clone(), hop[1].clone())?;
+				Self::update_swap_reserves(pool, &hop[0], amount[0], amount[1])?;
+			}
+
 			Ok(())
 		}
 
+		//transfers the input of a path from the sender to the pallet and the output back to the
+		//sender, updating the reserves of every pool in between
+		pub fn swap_along_path(
+			sender: &AccountIdOf<T>,
+			path: &[AssetKindOf<T>],
+			amounts: &[BalanceOf<T>],
+		) -> DispatchResult {
+			let pallet_account = Self::account_id();
+			let asset_in = path.first().ok_or(Error::<T>::InvalidPath)?;
+			let asset_out = path.last().ok_or(Error::<T>::InvalidPath)?;
+			let amount_in = amounts.first().cloned().ok_or(Error::<T>::InvalidPath)?;
+			let amount_out = amounts.last().cloned().ok_or(Error::<T>::InvalidPath)?;
+
+			//transfer the input from sender to pallet
+			Self::transfer_asset_kind(asset_in, sender, &pallet_account, amount_in, true)?;
+
+			//transfer the output from pallet to sender
+			Self::transfer_asset_kind(asset_out, &pallet_account, sender, amount_out, true)?;
+
+			//update the reserves of every pool in the path
+			Self::update_path_reserves(path, amounts)
+		}
+
+		//calculates (a * b) / c using 256-bit wide intermediates, so the multiplication can never
+		//overflow before the division takes place. Fails if c is zero or the result does not fit
+		//into the balance type
+		pub fn multiply_by_rational(
+			a: BalanceOf<T>,
+			b: BalanceOf<T>,
+			c: BalanceOf<T>,
+			rounding: Rounding,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			let result = multiply_by_rational_with_rounding(
+				a.saturated_into::<u128>(),
+				b.saturated_into::<u128>(),
+				c.saturated_into::<u128>(),
+				rounding,
+			)
+			.ok_or(Error::<T>::OperationOverflow)?;
+
+			<BalanceOf<T> as TryFrom<u128>>::try_from(result)
+				.map_err(|_| Error::<T>::OperationOverflow)
+		}
+
 		pub fn get_input_convert(
 			input_amount: BalanceOf<T>,
 			input_reserve: BalanceOf<T>,
 			output_reserve: BalanceOf<T>,
+			fee: Permill,
 		) -> Result<BalanceOf<T>, Error<T>> {
-
-			//Thousand and Fee are constants used to represent the percentage fee
+			//One and Fee are the parts of the pool's Permill fee (One = 1_000_000)
 			//The math function to calculate the asset_amount is:
-			//asset_amount = ((Thousand - Fee) * ∆x * y) / ((Thousand - Fee) * x + (Thousand - Fee) * ∆x)
+			//asset_amount = ((One - Fee) * ∆x * y) / (One * x + (One - Fee) * ∆x)
 
 			//∆x = currency_amount (input_amount)
 			//x = currency pool amount (input_reserve)
 			//y = asset pool amount (output_reserve)
 
-			//(Thousand - Fee)
-			let percentage_less_fee = T::Thousand::get()
-				.checked_sub(&T::Fee::get())
-				.ok_or(Error::<T>::OperationOverflow)?;
+			//(One - Fee)
+			let percentage_less_fee = <BalanceOf<T>>::from(fee.left_from_one().deconstruct());
 
-			//(Thousand - Fee) * ∆x
+			//(One - Fee) * ∆x
 			let mult_amount = percentage_less_fee
 				.checked_mul(&input_amount)
 				.ok_or(Error::<T>::OperationOverflow)?;
 
-			//((Thousand - Fee) * ∆x * y)
-			let numerator =
-				mult_amount.checked_mul(&output_reserve).ok_or(Error::<T>::OperationOverflow)?;
-
-			//(Thousand - Fee) * x
-			let mult_reserve = percentage_less_fee
+			//One * x
+			let mult_reserve = <BalanceOf<T>>::from(Permill::ACCURACY)
 				.checked_mul(&input_reserve)
 				.ok_or(Error::<T>::OperationOverflow)?;
 
-			//((Thousand - Fee) * x + (Thousand - Fee) * ∆x)
+			//(One * x + (One - Fee) * ∆x)
 			let denominator =
 				mult_reserve.checked_add(&mult_amount).ok_or(Error::<T>::OperationOverflow)?;
 
-			//((Thousand - Fee) * ∆x * y) / ((Thousand - Fee) * x + (Thousand - Fee) * ∆x)
-			let final_amount =
-			numerator.checked_div(&denominator).ok_or(Error::<T>::OperationOverflow)?;
+			//((One - Fee) * ∆x * y) / (One * x + (One - Fee) * ∆x)
+			Self::multiply_by_rational(mult_amount, output_reserve, denominator, Rounding::Down)
+		}
+
+		pub fn get_output_convert(
+			output_amount: BalanceOf<T>,
+			input_reserve: BalanceOf<T>,
+			output_reserve: BalanceOf<T>,
+			fee: Permill,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			//inverse of get_input_convert, calculates the input needed to receive an exact output
+			//The math function to calculate the input_amount is:
+			//input_amount = (One * x * ∆y) / ((One - Fee) * (y - ∆y)), rounded up
+
+			//∆y = asset amount to receive (output_amount)
+			//x = currency pool amount (input_reserve)
+			//y = asset pool amount (output_reserve)
+
+			//(One - Fee)
+			let percentage_less_fee = <BalanceOf<T>>::from(fee.left_from_one().deconstruct());
 
-			Ok(final_amount)
+			//the pool cannot give away its whole reserve
+			ensure!(output_amount < output_reserve, Error::<T>::InsufficientPoolReserve);
+
+			//One * x
+			let mult_reserve = <BalanceOf<T>>::from(Permill::ACCURACY)
+				.checked_mul(&input_reserve)
+				.ok_or(Error::<T>::OperationOverflow)?;
+
+			//(One - Fee) * (y - ∆y)
+			let denominator = output_reserve
+				.checked_sub(&output_amount)
+				.and_then(|amount| amount.checked_mul(&percentage_less_fee))
+				.ok_or(Error::<T>::OperationOverflow)?;
+
+			//(One * x * ∆y) / ((One - Fee) * (y - ∆y)), rounded up in favour of the pool
+			Self::multiply_by_rational(mult_reserve, output_amount, denominator, Rounding::Up)
 		}
 
+		//kept for the callers of the first version of the dex, the ratio loses all the precision
+		//of the reserves, for example a pool of 1500/1000 reports 1/1
+		#[deprecated(note = "use `spot_price`, `quote` or `twap` instead")]
 		pub fn price_oracle(
 			asset_id: AssetIdOf<T>,
 		) -> Result<OraclePrice<AssetIdOf<T>, BalanceOf<T>>, Error<T>> {
-
 			//get the pool associated to asset_id and extract reserves
-			let pool = <PoolsMap<T>>::get(asset_id.clone()).ok_or(Error::<T>::PoolNotFound)?;
-			let currency_amount = pool.currency_reserve.clone();
-			let asset_amount = pool.asset_reserve.clone();
+			let pool = Self::get_pool(AssetKind::Native, AssetKind::Asset(asset_id.clone()))?;
+			let (currency_amount, asset_amount) = Self::get_reserves(&pool, &AssetKind::Native);
 
-			//calculate the common minimum between both reserves 
+			//calculate the common minimum between both reserves
 			let minimum = currency_amount.min(asset_amount);
 
 			//divide both reserves by the minimum
@@ -860,5 +4377,314 @@ pub mod pallet {
 
 			Ok(oracle)
 		}
+
+		//current price of a fungible asset in both directions, as (price of the asset in currency,
+		//price of the currency in the asset). Fees are not included, see quote for that
+		pub fn spot_price(asset_id: AssetIdOf<T>) -> Result<(FixedU128, FixedU128), Error<T>> {
+			let pool = Self::get_pool(AssetKind::Native, AssetKind::Asset(asset_id))?;
+			let (currency_reserve, asset_reserve) = Self::get_reserves(&pool, &AssetKind::Native);
+
+			let asset_price = FixedU128::checked_from_rational(currency_reserve, asset_reserve)
+				.ok_or(Error::<T>::InsufficientPoolReserve)?;
+			let currency_price = FixedU128::checked_from_rational(asset_reserve, currency_reserve)
+				.ok_or(Error::<T>::InsufficientPoolReserve)?;
+
+			Ok((asset_price, currency_price))
+		}
+
+		//amount received by swapping amount_in along the path, with the fee of every pool
+		pub fn quote(
+			amount_in: BalanceOf<T>,
+			path: &[AssetKindOf<T>],
+		) -> Result<BalanceOf<T>, DispatchError> {
+			Self::ensure_valid_path(path)?;
+			let amounts = Self::get_amounts_out(path, amount_in)?;
+			amounts.last().cloned().ok_or_else(|| Error::<T>::InvalidPath.into())
+		}
+
+		//amount to swap along the path to receive amount_out, with the fee of every pool
+		pub fn quote_exact_out(
+			amount_out: BalanceOf<T>,
+			path: &[AssetKindOf<T>],
+		) -> Result<BalanceOf<T>, DispatchError> {
+			Self::ensure_valid_path(path)?;
+			let amounts = Self::get_amounts_in(path, amount_out)?;
+			amounts.first().cloned().ok_or_else(|| Error::<T>::InvalidPath.into())
+		}
+
+		//amounts of asset_a and asset_b that burning liquidity_amount of the pool gives back
+		pub fn liquidity_value(
+			pool: &PoolOf<T>,
+			liquidity_amount: BalanceOf<T>,
+		) -> Result<(BalanceOf<T>, BalanceOf<T>), Error<T>> {
+			//get the total issuance of liquidity asset from the pool
+			let asset_total_issuance =
+				T::Fungibles::total_issuance(pool.liquidity_asset_id.clone());
+
+			//amount_a = liquidity_amount * reserve_a / total_issuance, rounded down
+			let amount_a = Self::multiply_by_rational(
+				liquidity_amount,
+				pool.reserve_a,
+				asset_total_issuance,
+				Rounding::Down,
+			)?;
+
+			//amount_b = liquidity_amount * reserve_b / total_issuance, rounded down
+			let amount_b = Self::multiply_by_rational(
+				liquidity_amount,
+				pool.reserve_b,
+				asset_total_issuance,
+				Rounding::Down,
+			)?;
+
+			Ok((amount_a, amount_b))
+		}
+
+		//a pool as returned by the runtime api
+		pub fn pool_info(pool: PoolOf<T>) -> PoolInfoOf<T> {
+			PoolInfo {
+				liquidity_supply: T::Fungibles::total_issuance(pool.liquidity_asset_id.clone()),
+				paused: Self::paused() || pool.status == PoolStatus::Paused,
+				asset_a: pool.asset_a,
+				asset_b: pool.asset_b,
+				reserve_a: pool.reserve_a,
+				reserve_b: pool.reserve_b,
+				liquidity_asset_id: pool.liquidity_asset_id,
+				fee: pool.fee,
+			}
+		}
+
+		//every pool as returned by the runtime api
+		pub fn list_pools() -> Vec<PoolInfoOf<T>> {
+			<PoolsMap<T>>::iter_values().map(Self::pool_info).collect()
+		}
+
+		//cumulative prices of a pool at the given time, the current price lasted since the last
+		//update. A pool without reserves or without a first update does not accumulate
+		pub fn cumulative_prices(pool: &PoolOf<T>, now: u64) -> (FixedU128, FixedU128) {
+			let elapsed = now.saturating_sub(pool.last_timestamp);
+			let has_reserves = !pool.reserve_a.is_zero() && !pool.reserve_b.is_zero();
+			if pool.last_timestamp.is_zero() || elapsed.is_zero() || !has_reserves {
+				return (pool.price_a_cumulative, pool.price_b_cumulative)
+			}
+
+			let price_a = FixedU128::saturating_from_rational(pool.reserve_b, pool.reserve_a);
+			let price_b = FixedU128::saturating_from_rational(pool.reserve_a, pool.reserve_b);
+
+			//like in uniswap v2, the accumulators wrap instead of failing
+			let accumulate = |cumulative: FixedU128, price: FixedU128| {
+				FixedU128::from_inner(
+					cumulative
+						.into_inner()
+						.wrapping_add(price.into_inner().wrapping_mul(elapsed as u128)),
+				)
+			};
+
+			(
+				accumulate(pool.price_a_cumulative, price_a),
+				accumulate(pool.price_b_cumulative, price_b),
+			)
+		}
+
+		//updates the cumulative prices of a pool on its first update of every block, and saves
+		//them as a new observation
+		pub fn update_cumulative_prices(pool: &mut PoolOf<T>) {
+			let now = T::UnixTime::now().as_secs();
+			if now <= pool.last_timestamp {
+				return
+			}
+
+			let (price_a_cumulative, price_b_cumulative) = Self::cumulative_prices(pool, now);
+			pool.price_a_cumulative = price_a_cumulative;
+			pool.price_b_cumulative = price_b_cumulative;
+			pool.last_timestamp = now;
+
+			//the buffer grows until it reaches its cardinality, then the oldest observation is
+			//overwritten
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+			let observation = Observation {
+				block_number: frame_system::Pallet::<T>::block_number(),
+				timestamp: now,
+				price_a_cumulative,
+				price_b_cumulative,
+			};
+			let mut state = <ObservationStates<T>>::get(&pool_id);
+			<PriceObservations<T>>::mutate(&pool_id, |observations| {
+				let next = state.index.saturating_add(1);
+				if observations.is_empty() {
+					state.index = 0;
+					let _ = observations.try_push(observation);
+				} else if (next as usize) < observations.len() {
+					state.index = next;
+					observations[next as usize] = observation;
+				} else if (observations.len() as u32) < state.cardinality_next &&
+					observations.try_push(observation.clone()).is_ok()
+				{
+					state.index = next;
+				} else {
+					state.index = 0;
+					observations[0] = observation;
+				}
+			});
+			<ObservationStates<T>>::insert(&pool_id, state);
+		}
+
+		//cumulative prices of a fungible asset pool seconds_ago seconds before now, for every
+		//entry of seconds_ago. Times between two observations are interpolated and times after
+		//the last update of the pool are extrapolated with its current price
+		pub fn observe(
+			asset_id: AssetIdOf<T>,
+			seconds_ago: Vec<u64>,
+		) -> Result<Vec<(FixedU128, FixedU128)>, Error<T>> {
+			let pool = Self::get_pool(AssetKind::Native, AssetKind::Asset(asset_id))?;
+			let pool_id = (pool.asset_a.clone(), pool.asset_b.clone());
+			let now = T::UnixTime::now().as_secs();
+
+			//observations from the oldest to the newest
+			let mut observations = <PriceObservations<T>>::get(&pool_id).into_inner();
+			let oldest = (<ObservationStates<T>>::get(&pool_id).index as usize)
+				.saturating_add(1)
+				.checked_rem(observations.len())
+				.unwrap_or_default();
+			observations.rotate_left(oldest);
+
+			seconds_ago
+				.into_iter()
+				.map(|ago| {
+					let target =
+						now.checked_sub(ago).ok_or(Error::<T>::InsufficientPriceHistory)?;
+					if target >= pool.last_timestamp {
+						return Ok(Self::cumulative_prices(&pool, target))
+					}
+					Self::interpolate_observations(&observations, target)
+				})
+				.collect()
+		}
+
+		//cumulative prices at the target time, between the two observations around it
+		fn interpolate_observations(
+			observations: &[ObservationOf<T>],
+			target: u64,
+		) -> Result<(FixedU128, FixedU128), Error<T>> {
+			let after = observations
+				.iter()
+				.position(|observation| observation.timestamp >= target)
+				.ok_or(Error::<T>::InsufficientPriceHistory)?;
+			let after = &observations[after];
+			if after.timestamp == target {
+				return Ok((after.price_a_cumulative, after.price_b_cumulative))
+			}
+			let before = observations
+				.iter()
+				.rev()
+				.find(|observation| observation.timestamp < target)
+				.ok_or(Error::<T>::InsufficientPriceHistory)?;
+
+			//the accumulators grow linearly between two observations
+			let elapsed = (target - before.timestamp) as u128;
+			let span = (after.timestamp - before.timestamp) as u128;
+			let interpolate = |before: FixedU128, after: FixedU128| {
+				let delta = after.into_inner().wrapping_sub(before.into_inner());
+				multiply_by_rational_with_rounding(delta, elapsed, span, Rounding::Down)
+					.map(|delta| FixedU128::from_inner(before.into_inner().wrapping_add(delta)))
+					.ok_or(Error::<T>::InsufficientPriceHistory)
+			};
+
+			Ok((
+				interpolate(before.price_a_cumulative, after.price_a_cumulative)?,
+				interpolate(before.price_b_cumulative, after.price_b_cumulative)?,
+			))
+		}
+
+		//time-weighted average price of a fungible asset in currency over the last window
+		//seconds
+		pub fn twap(asset_id: AssetIdOf<T>, window: u64) -> Result<FixedU128, Error<T>> {
+			ensure!(!window.is_zero(), Error::<T>::InsufficientPriceHistory);
+			let cumulatives = Self::observe(asset_id, vec![window, 0])?;
+
+			//the asset is asset_b of a currency pool, so its price is the one of side b
+			let (start, end) = (cumulatives[0].1, cumulatives[1].1);
+			let price_seconds = end.into_inner().wrapping_sub(start.into_inner());
+
+			Ok(FixedU128::from_inner(price_seconds / window as u128))
+		}
+	}
+}
+
+/// Signed extension that rejects dex calls whose deadline has already passed, so that expired
+/// operations are dropped by the transaction pool instead of being included in a block.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckDeadline<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckDeadline<T> {
+	/// Create a new `CheckDeadline` extension.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckDeadline<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> Debug for CheckDeadline<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckDeadline")
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckDeadline<T>
+where
+	<T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckDeadline";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		let deadline = match call.is_sub_type().and_then(Pallet::<T>::call_deadline) {
+			Some(deadline) => deadline,
+			None => return Ok(ValidTransaction::default()),
+		};
+
+		let now = frame_system::Pallet::<T>::block_number();
+		if now > deadline {
+			return InvalidTransaction::Stale.into();
+		}
+
+		//keep the transaction in the pool only until its deadline
+		let longevity = (deadline - now).saturated_into::<u64>().saturating_add(1);
+		Ok(ValidTransaction { longevity, ..Default::default() })
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
 	}
 }
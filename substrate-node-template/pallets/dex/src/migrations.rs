@@ -0,0 +1,123 @@
+//! Storage migrations of the dex pallet.
+
+use super::*;
+use frame_support::{
+	pallet_prelude::*,
+	storage_alias,
+	traits::{OnRuntimeUpgrade, StorageVersion},
+};
+use sp_std::vec::Vec;
+
+pub mod v1 {
+	use super::*;
+	use sp_arithmetic::{traits::Zero, FixedU128};
+
+	//pool layout before version 1, when every pool was paired with the currency and keyed by
+	//the asset_id of its fungible asset
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct OldPool<AssetId, AssetBalance> {
+		pub asset_id: AssetId,
+		pub currency_reserve: AssetBalance,
+		pub asset_reserve: AssetBalance,
+		pub liquidity_asset_id: AssetId,
+	}
+
+	#[storage_alias]
+	pub type PoolsMap<T: Config> = StorageMap<
+		Pallet<T>,
+		Twox64Concat,
+		AssetIdOf<T>,
+		OldPool<AssetIdOf<T>, BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	/// Moves every currency pool from its `asset_id` key to the `(Native, Asset(asset_id))` pair
+	/// key, keeping its reserves and liquidity asset. Migrated pools are active, charge the
+	/// default fee, which was the fee charged by every pool until now, and start their cumulative
+	/// prices and price history empty. Every other storage item of the pallet is new and starts
+	/// with its default value.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() >= StorageVersion::new(1) {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let fee = Pallet::<T>::parameters().default_fee;
+
+			//both maps share the same prefix, so the old pools are drained before inserting
+			//the new ones
+			let old_pools: Vec<_> = PoolsMap::<T>::drain().collect();
+			let migrated = old_pools.len() as u64;
+
+			for (asset_id, old_pool) in old_pools {
+				let pool = Pool {
+					asset_a: AssetKind::Native,
+					asset_b: AssetKind::Asset(asset_id),
+					reserve_a: old_pool.currency_reserve,
+					reserve_b: old_pool.asset_reserve,
+					liquidity_asset_id: old_pool.liquidity_asset_id,
+					fee,
+					status: PoolStatus::Active,
+					price_a_cumulative: FixedU128::zero(),
+					price_b_cumulative: FixedU128::zero(),
+					last_timestamp: 0,
+				};
+				crate::PoolsMap::<T>::insert((pool.asset_a.clone(), pool.asset_b.clone()), pool);
+			}
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			T::DbWeight::get().reads_writes(migrated + 2, migrated * 2 + 1)
+		}
+
+		//records the reserves of every pool before the upgrade
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			if StorageVersion::get::<Pallet<T>>() >= StorageVersion::new(1) {
+				return Ok(Vec::new())
+			}
+
+			let reserves: Vec<(AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>)> = PoolsMap::<T>::iter()
+				.map(|(asset_id, old_pool)| {
+					(asset_id, old_pool.currency_reserve, old_pool.asset_reserve)
+				})
+				.collect();
+
+			Ok(reserves.encode())
+		}
+
+		//checks that no pool was lost and that every pool kept its reserves
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			ensure!(
+				StorageVersion::get::<Pallet<T>>() >= StorageVersion::new(1),
+				"the storage version was not updated"
+			);
+			if state.is_empty() {
+				return Ok(())
+			}
+
+			let reserves =
+				Vec::<(AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>)>::decode(&mut &state[..])
+					.map_err(|_| "the pre upgrade state can not be decoded")?;
+
+			ensure!(
+				crate::PoolsMap::<T>::iter().count() == reserves.len(),
+				"the number of pools changed"
+			);
+			for (asset_id, currency_reserve, asset_reserve) in reserves {
+				let pool =
+					crate::PoolsMap::<T>::get((AssetKind::Native, AssetKind::Asset(asset_id)))
+						.ok_or("a pool was not migrated")?;
+				ensure!(
+					pool.reserve_a == currency_reserve && pool.reserve_b == asset_reserve,
+					"the reserves of a pool changed"
+				);
+			}
+
+			Ok(())
+		}
+	}
+}
@@ -1,18 +1,22 @@
 use crate as pallet_dex;
 use frame_support::{
 	parameter_types,
-	traits::{AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64},
+	traits::{
+		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64, ConstU8, GenesisBuild,
+	},
 	PalletId,
 };
 use frame_system::{EnsureRoot, EnsureSigned};
 use sp_core::H256;
 use sp_runtime::{
-	testing::Header,
+	testing::{Header, TestSignature, TestXt, UintAuthorityId},
 	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError, Permill,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
 
 type Balance = u128;
 
@@ -24,6 +28,7 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system,
+		Timestamp: pallet_timestamp,
 		Balances: pallet_balances,
 		Assets: pallet_assets,
 		Dex: pallet_dex//::{Pallet, Call, Storage, Event<T>},
@@ -57,6 +62,13 @@ impl frame_system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<1>;
+	type WeightInfo = ();
+}
+
 parameter_types! {
 	pub const ExistentialDeposit: u64 = 1;
 	pub const MaxLocks: u32 = 10;
@@ -77,13 +89,80 @@ parameter_types! {
 	pub const AssetDeposit: Balance = 100;
 	pub const ApprovalDeposit: Balance = 1;
 	pub const StringLimit: u32 = 50;
-	pub const MetadataDepositBase: Balance = 10;
-	pub const MetadataDepositPerByte: Balance = 1;
+	pub static MetadataDepositBase: Balance = 0;
+	pub const MetadataDepositPerByte: Balance = 0;
 	pub const PalletIdentification: PalletId = PalletId(*b"palle/*t");
-	pub const FeeConst: Balance = 3;
-	pub const ThousandConst: Balance = 1000;
+	pub const ProtocolFeeRecipientAccount: u64 = 100;
+	pub const DustRecipientAccount: u64 = 101;
+	pub static PoolCreationDeposit: Balance = 0;
+	pub static MinimumLiquidity: Balance = 10;
+	pub static RestrictListings: bool = false;
+	pub static FlashSwapRepayment: (Balance, Balance) = (0, 0);
+	pub static FlashSwapReenters: Option<u32> = None;
+	pub static FlashSwapNests: Option<u32> = None;
+	pub const KeeperFee: Permill = Permill::from_percent(1);
+}
+
+//repays the amounts set in FlashSwapRepayment. When FlashSwapNests is set it first borrows an
+//asset of the currency pool of that asset with a nested flash swap, and when FlashSwapReenters is
+//set it tries to swap on the currency pool of that asset
+pub struct MockFlashSwapHandler;
+
+impl pallet_dex::FlashSwapHandler<u64, pallet_dex::AssetKind<u32>, Balance>
+	for MockFlashSwapHandler
+{
+	fn on_flash_swap(
+		borrower: &u64,
+		_asset_a: &pallet_dex::AssetKind<u32>,
+		_asset_b: &pallet_dex::AssetKind<u32>,
+		_amount_a: Balance,
+		_amount_b: Balance,
+		_data: &[u8],
+	) -> Result<(Balance, Balance), DispatchError> {
+		if let Some(asset_id) = FlashSwapNests::get() {
+			FlashSwapNests::set(None);
+			Dex::flash_swap(
+				RuntimeOrigin::signed(*borrower),
+				pallet_dex::AssetKind::Native,
+				pallet_dex::AssetKind::Asset(asset_id),
+				0,
+				1,
+				vec![],
+				None,
+			)?;
+		}
+		if let Some(asset_id) = FlashSwapReenters::get() {
+			Dex::currency_to_asset(RuntimeOrigin::signed(*borrower), 10, asset_id, 0, None)?;
+		}
+		Ok(FlashSwapRepayment::get())
+	}
 }
 
+impl frame_system::offchain::SigningTypes for Test {
+	type Public = UintAuthorityId;
+	type Signature = TestSignature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+	RuntimeCall: From<LocalCall>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = Extrinsic;
+}
+
+//keepers sign with the UintAuthorityId keys set in the tests, their account is the id itself
+pub struct MockKeeperId;
+
+impl frame_system::offchain::AppCrypto<UintAuthorityId, TestSignature> for MockKeeperId {
+	type RuntimeAppPublic = UintAuthorityId;
+	type GenericPublic = UintAuthorityId;
+	type GenericSignature = TestSignature;
+}
+
+//fee of the pools created in the tests, the same 3/1000 that was charged before fee tiers
+pub const FEE: Permill = Permill::from_parts(3_000);
+
 impl pallet_assets::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type Balance = u128;
@@ -111,11 +190,49 @@ impl pallet_dex::Config for Test {
 	type AssetId = u32;
 	type AssetBalance = u128;
 	type PalletId = PalletIdentification;
-	type Fee = FeeConst;
-	type Thousand = ThousandConst;
+	type MaxHops = ConstU32<4>;
+	type MaxFeeTiers = ConstU32<8>;
+	type ProtocolFeeRecipient = ProtocolFeeRecipientAccount;
+	type AdminOrigin = EnsureRoot<Self::AccountId>;
+	type MinimumLiquidity = MinimumLiquidity;
+	type FirstLiquidityAssetId = ConstU32<1_000>;
+	type NativeDecimals = ConstU8<12>;
+	type StringLimit = StringLimit;
+	type DustRecipient = DustRecipientAccount;
+	type DestroyGracePeriod = ConstU64<10>;
+	type DestroyItemsLimit = ConstU32<10>;
+	type PoolCreateOrigin = EnsureSigned<Self::AccountId>;
+	type PoolCreationDeposit = PoolCreationDeposit;
+	type RestrictListings = RestrictListings;
+	type PauseOrigin = EnsureRoot<Self::AccountId>;
+	type UnixTime = Timestamp;
+	type MaxObservations = ConstU32<4>;
+	type FlashSwapHandler = MockFlashSwapHandler;
+	type MaxOrdersPerBook = ConstU32<4>;
+	type KeeperAuthorityId = MockKeeperId;
+	type KeeperFee = KeeperFee;
+	type KeeperPriority = ConstU64<100>;
+	type MaxDcaPerBlock = ConstU32<4>;
+	type DcaDeposit = ConstU128<5>;
+	type MaxDcaSkips = ConstU32<2>;
+	type CommitBond = ConstU128<10>;
+	type RevealWindow = ConstU64<5>;
+	type MaxCommitsPerBlock = ConstU32<4>;
+	type MaxBatchSwaps = ConstU32<4>;
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_dex::GenesisConfig::<Test> {
+		assets: vec![],
+		initial_amount: 0,
+		fee_tiers: vec![Permill::from_parts(500), FEE, Permill::from_percent(1)],
+		keepers: vec![],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	storage.into()
 }
@@ -1,11 +1,22 @@
-use crate::{mock::*, Error, Event, Pool, OraclePrice, PoolsMap};
+use crate::{
+	migrations, mock::*, AssetKind, CheckDeadline, DcaEnd, Error, Event, KeeperPayload,
+	OraclePrice, Parameters, Pool, PoolStatus, PoolsMap, SwapParams,
+};
+use codec::{Decode, Encode};
 use frame_support::{
 	assert_noop, assert_ok,
 	traits::{
 		fungibles::{self, *},
-		Currency,
+		Currency, Get, Hooks, OnRuntimeUpgrade, ReservableCurrency, StorageVersion,
 	},
 };
+use sp_core::offchain::{testing::TestTransactionPoolExt, TransactionPoolExt};
+use sp_runtime::{
+	testing::{TestSignature, UintAuthorityId},
+	traits::{SignedExtension, ValidateUnsigned},
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	FixedPointNumber, FixedU128, Permill,
+};
 
 #[test]
 fn create_pool_successfully() {
@@ -13,7 +24,7 @@ fn create_pool_successfully() {
 		System::set_block_number(1);
 
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
+		let liquidity_asset_id = 1000u32;
 		let account_id = 1u64;
 
 		//create a sender
@@ -31,21 +42,48 @@ fn create_pool_successfully() {
 		);
 
 		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender, asset_id, liquidity_asset_id, 50u128, 50u128));
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
 
 		//verify the sender balances changed
 		assert_eq!(<Test as crate::Config>::Currency::free_balance(&account_id), 50u128);
 		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &account_id), 50u128);
 
 		//rcheck the new pool values are correct
-		let pool =
-			Pool { asset_id, liquidity_asset_id, asset_reserve: 50u128, currency_reserve: 50u128 };
+		let pool = Pool {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			reserve_a: 50u128,
+			reserve_b: 50u128,
+			liquidity_asset_id,
+			fee: FEE,
+			status: PoolStatus::Active,
+			..Default::default()
+		};
 
 		//compare both pools to check values
-		assert_eq!(Dex::pools(asset_id).unwrap_or_default(), pool);
+		assert_eq!(
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default(),
+			pool
+		);
 
 		//check last event
-		System::assert_last_event(Event::PoolCreated { asset_id, liquidity_asset_id }.into());
+		System::assert_last_event(
+			Event::PoolCreated {
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				liquidity_asset_id,
+				fee: FEE,
+			}
+			.into(),
+		);
 	})
 }
 
@@ -54,8 +92,6 @@ fn create_pool_successfully() {
 fn create_pool_fails_existing_pool() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
-		let second_liquidity_asset_id = 5u32;
 		let account_id = 1u64;
 
 		//create a sender
@@ -73,21 +109,36 @@ fn create_pool_fails_existing_pool() {
 		);
 
 		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128));
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
 
 		//try to create another pool with the same asset_id
 		assert_noop!(
-			Dex::create_pool(sender, asset_id, second_liquidity_asset_id, 10u128, 10u128),
+			Dex::create_pool(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				10u128,
+				10u128,
+				None
+			),
 			Error::<Test>::PoolAlreadyExists
 		);
 	})
 }
 
 #[test]
-fn create_pool_fails_existing_liq_asset() {
+fn create_pool_allocates_the_next_free_liquidity_asset() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
 		let second_asset_id = 5u32;
 		let account_id = 1u64;
 
@@ -97,21 +148,133 @@ fn create_pool_fails_existing_liq_asset() {
 		//transfer currency to the sender
 		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
 
-		//create an asset
+		//create the assets, the first free id is already taken by an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert_ok!(Dex::create_asset_helper(second_asset_id));
+		assert_ok!(Dex::create_asset_helper(1000u32));
+		assert_ok!(Assets::set_metadata(
+			RuntimeOrigin::signed(Dex::account_id()),
+			asset_id.into(),
+			b"Token".to_vec(),
+			b"TKN".to_vec(),
+			6
+		));
 
-		//mint asset to user
+		//mint assets to user
 		assert!(
 			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
 		);
+		assert!(<Test as crate::Config>::Fungibles::mint_into(
+			second_asset_id,
+			&account_id,
+			100u128
+		)
+		.is_ok());
 
-		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128));
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native,
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(second_asset_id),
+			Some(FEE),
+			20u128,
+			20u128,
+			None
+		));
+
+		//the taken id is skipped and the counter moves past the allocated ids
+		let first_pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap();
+		let second_pool =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(second_asset_id))).unwrap();
+		assert_eq!(first_pool.liquidity_asset_id, 1001u32);
+		assert_eq!(second_pool.liquidity_asset_id, 1002u32);
+		assert_eq!(Dex::next_liquidity_asset_id(), 1003u32);
+
+		//the liquidity asset is named after the pair and uses the decimals of the currency
+		assert_eq!(<Test as crate::Config>::Fungibles::symbol(&1001u32), b"LP-TKN".to_vec());
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::name(&1001u32),
+			b"Dex liquidity TKN".to_vec()
+		);
+		assert_eq!(<Test as crate::Config>::Fungibles::decimals(&1001u32), 12);
+
+		//an asset without symbol gets a placeholder
+		assert_eq!(<Test as crate::Config>::Fungibles::symbol(&1002u32), b"LP-ASSET".to_vec());
+	})
+}
+
+#[test]
+fn create_pool_fits_the_liquidity_asset_metadata_and_charges_its_deposit() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
 
-		//try to create another pool with the same liquidity_asset_id
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 1_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert_ok!(Assets::set_metadata(
+			RuntimeOrigin::signed(Dex::account_id()),
+			asset_id.into(),
+			b"Token".to_vec(),
+			vec![b'T'; 48],
+			6
+		));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 1_000u128).is_ok()
+		);
+
+		//the creator pays the metadata deposit, the pallet account keeps the whole reserves
+		MetadataDepositBase::set(5);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+		MetadataDepositBase::set(0);
+		assert_eq!(Balances::free_balance(account_id), 1_000u128 - 100u128 - 5u128);
+		assert_eq!(Balances::free_balance(Dex::account_id()), 100u128);
+		assert_eq!(Balances::reserved_balance(Dex::account_id()), 5u128);
+
+		//name and symbol are cut to the StringLimit
+		let symbol = <Test as crate::Config>::Fungibles::symbol(&1000u32);
+		assert_eq!(symbol, [b"LP-".to_vec(), vec![b'T'; 47]].concat());
+		assert_eq!(<Test as crate::Config>::Fungibles::name(&1000u32).len(), 50);
+
+		//the search of a free liquidity asset id is bounded
+		let second_asset_id = 5u32;
+		assert_ok!(Dex::create_asset_helper(second_asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(
+			second_asset_id,
+			&account_id,
+			1_000u128
+		)
+		.is_ok());
+		for taken_id in 1001u32..=1001 + crate::MAX_LIQUIDITY_ASSET_ID_PROBES {
+			assert_ok!(Dex::create_asset_helper(taken_id));
+		}
 		assert_noop!(
-			Dex::create_pool(sender, second_asset_id, liquidity_asset_id, 10u128, 10u128),
-			Error::<Test>::AssetAlreadyExists
+			Dex::create_pool(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(second_asset_id),
+				Some(FEE),
+				100u128,
+				100u128,
+				None
+			),
+			Error::<Test>::LiquidityAssetIdUnavailable
 		);
 	})
 }
@@ -120,7 +283,6 @@ fn create_pool_fails_existing_liq_asset() {
 fn create_pool_fails_asset_not_found() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
 		let account_id = 1u64;
 
 		//create a sender
@@ -128,7 +290,15 @@ fn create_pool_fails_asset_not_found() {
 
 		//fails to create a pool with an non-existent asset
 		assert_noop!(
-			Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128),
+			Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				50u128,
+				50u128,
+				None
+			),
 			Error::<Test>::AssetNotFound
 		);
 	})
@@ -138,7 +308,6 @@ fn create_pool_fails_asset_not_found() {
 fn create_pool_fails_asset_amount_zero() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
 		let account_id = 1u64;
 
 		//create a sender
@@ -157,7 +326,15 @@ fn create_pool_fails_asset_amount_zero() {
 
 		//fails to create a pool because of the zero asset amount
 		assert_noop!(
-			Dex::create_pool(sender, asset_id, liquidity_asset_id, 50u128, 0u128),
+			Dex::create_pool(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				50u128,
+				0u128,
+				None
+			),
 			Error::<Test>::AssetAmountZero
 		);
 	})
@@ -168,7 +345,6 @@ fn create_pool_fails_asset_amount_zero() {
 fn create_pool_fails_currency_amount_zero() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
 		let account_id = 1u64;
 
 		//create a sender
@@ -187,7 +363,15 @@ fn create_pool_fails_currency_amount_zero() {
 
 		//fails to create a pool because of the zero currency amount
 		assert_noop!(
-			Dex::create_pool(sender, asset_id, liquidity_asset_id, 0u128, 50u128),
+			Dex::create_pool(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				0u128,
+				50u128,
+				None
+			),
 			Error::<Test>::CurrencyAmountZero
 		);
 	})
@@ -199,7 +383,7 @@ fn add_liquidity_successfully() {
 		System::set_block_number(1);
 
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
+		let liquidity_asset_id = 1000u32;
 		let account_id = 1u64;
 
 		//create a sender
@@ -217,35 +401,52 @@ fn add_liquidity_successfully() {
 		);
 
 		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128));
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
 
 		//add liquidity to the pool created previously
-		assert_ok!(Dex::add_liquidity(sender, asset_id, 10u128));
+		assert_ok!(Dex::add_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			10u128,
+			10u128,
+			10u128,
+			None
+		));
 
 		//get the pool
-		let pool = Dex::pools(asset_id).unwrap_or_default();
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
 
-		//check that the new asset amount to add is (currency_amount/ currency_reserve) *
-		// asset_reserve
-		let asset_amount_to_add = ((10u128 / 50u128) * 50u128) + 1;
+		//check that the new asset amount to add is (currency_amount * asset_reserve) /
+		// currency_reserve
+		let asset_amount_to_add = (10u128 * 50u128) / 50u128;
 
-		//check that the new liquidity asset amount to mint is (currency_amount/ currency_reserve)
-		// *asset_liq_total_issuance
-		let liquidity_assets_to_add = (10u128 / 50u128) * 50u128;
+		//check that the new liquidity asset amount to mint is (currency_amount *
+		// asset_liq_total_issuance) / currency_reserve
+		let liquidity_assets_to_add = (10u128 * 50u128) / 50u128;
 
-		assert_eq!(pool.currency_reserve, 60u128);
-		assert_eq!(pool.asset_reserve, 50u128 + asset_amount_to_add);
+		assert_eq!(pool.reserve_a, 60u128);
+		assert_eq!(pool.reserve_b, 50u128 + asset_amount_to_add);
 		assert_eq!(
 			<Test as crate::Config>::Fungibles::balance(liquidity_asset_id, &account_id),
-			50u128 + liquidity_assets_to_add
+			50u128 - MinimumLiquidity::get() + liquidity_assets_to_add
 		);
 
 		System::assert_last_event(
 			Event::LiquidityAdded {
 				provider: account_id,
-				asset_id,
-				currency_amount: 10u128,
-				asset_amount: asset_amount_to_add,
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				amount_a: 10u128,
+				amount_b: asset_amount_to_add,
 				liquidity_minted: liquidity_assets_to_add,
 			}
 			.into(),
@@ -254,57 +455,136 @@ fn add_liquidity_successfully() {
 }
 
 #[test]
-fn add_liquidity_fails_pool_not_found() {
+fn add_liquidity_small_deposit_rounds_in_favour_of_the_pool() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
+		let liquidity_asset_id = 1000u32;
 		let account_id = 1u64;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
 		//transfer currency to the sender
-		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 2_000u128);
 
 		//create an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//fails to add liquidity because the pool doesn't exists
-		assert_noop!(Dex::add_liquidity(sender, asset_id, 10u128), Error::<Test>::PoolNotFound);
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool where the currency reserve is much bigger than the asset reserve
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			7u128,
+			None
+		));
+
+		//a deposit smaller than the reserve still mints liquidity
+		assert_ok!(Dex::add_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			1u128,
+			u128::MAX,
+			0u128,
+			None
+		));
+
+		//asset amount is ceil(1 * 7 / 1000) and liquidity minted is floor(1 * 1000 / 1000)
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 1_001u128);
+		assert_eq!(pool.reserve_b, 8u128);
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(liquidity_asset_id, &account_id),
+			1_001u128 - MinimumLiquidity::get()
+		);
 	})
 }
 
 #[test]
-fn add_liquidity_fails_currency_amount_zero() {
+fn add_liquidity_large_amounts_do_not_overflow() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
+		let liquidity_asset_id = 1000u32;
 		let account_id = 1u64;
+		let amount = 1u128 << 100;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
 		//transfer currency to the sender
-		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, amount * 4);
 
 		//create an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//fails to add liquidity because the currency amount is zero
-		assert_noop!(
-			Dex::add_liquidity(sender, asset_id, 0u128),
-			Error::<Test>::CurrencyAmountZero
+		//mint asset to user
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, amount * 4)
+			.is_ok());
+
+		//create a pool with reserves whose product does not fit into 128 bits
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			amount,
+			amount,
+			None
+		));
+
+		//add the same amount of liquidity again
+		assert_ok!(Dex::add_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			amount,
+			u128::MAX,
+			0u128,
+			None
+		));
+
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, amount * 2);
+		assert_eq!(pool.reserve_b, amount * 2);
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(liquidity_asset_id, &account_id),
+			amount * 2 - MinimumLiquidity::get()
 		);
+
+		//remove half of the liquidity
+		assert_ok!(Dex::remove_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			amount,
+			0u128,
+			0u128,
+			None
+		));
+
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, amount);
+		assert_eq!(pool.reserve_b, amount);
 	})
 }
 
 #[test]
-fn remove_liquidity_successfully() {
+fn add_liquidity_fails_result_overflow() {
 	new_test_ext().execute_with(|| {
-		System::set_block_number(1);
-
 		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
 		let account_id = 1u64;
 
+		//a pool of a single unit of currency cannot lock any liquidity
+		MinimumLiquidity::set(0);
+
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
@@ -315,62 +595,99 @@ fn remove_liquidity_successfully() {
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
 		//mint asset to user
-		assert!(
-			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
-		);
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 1u128 << 126)
+			.is_ok());
 
-		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128));
+		//create a pool where a single unit of currency is worth 2^126 units of asset
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1u128,
+			1u128 << 126,
+			None
+		));
 
-		//get the pool and reserves values before removing liquidity
-		let pool = Dex::pools(asset_id).unwrap_or_default();
-		let currency_reserve_before = pool.currency_reserve.clone();
-		let asset_reserve_before = pool.asset_reserve.clone();
+		//the required asset amount (4 * 2^126) does not fit into the balance type
+		assert_noop!(
+			Dex::add_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				4u128,
+				u128::MAX,
+				0u128,
+				None
+			),
+			Error::<Test>::OperationOverflow
+		);
+	})
+}
 
-		//remove liquidity
-		assert_ok!(Dex::remove_liquidity(sender, asset_id, 10u128));
+#[test]
+fn add_liquidity_fails_slippage_exceeded() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let account_id = 1u64;
 
-		//check that the new currency amount to remove is (liquidity_amount/
-		// asset_liq_total_issuance) * currency_reserve
-		let currency_amount_to_remove = (10u128 / 50u128) * pool.currency_reserve;
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
 
-		//check the new asset amount to remove is (liquidity_amount/ asset_liq_total_issuance) *
-		// asset_reserve
-		let asset_amount_to_remove = (10u128 / 50u128) * pool.asset_reserve;
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
 
-		//check the liq_assets were burnt
-		assert_eq!(<Test as crate::Config>::Fungibles::total_issuance(liquidity_asset_id), 40);
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//check the sender has new amount of currency and asset
-		assert_eq!(
-			<Test as crate::Config>::Currency::free_balance(&account_id),
-			50u128 + currency_amount_to_remove
-		);
-		assert_eq!(
-			<Test as crate::Config>::Fungibles::balance(asset_id, &account_id),
-			50u128 + asset_amount_to_remove
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
 		);
 
-		//check updated values of the pool
-		assert_eq!(pool.currency_reserve, currency_reserve_before - currency_amount_to_remove);
-		assert_eq!(pool.asset_reserve, asset_reserve_before - asset_amount_to_remove);
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
 
-		//check the last event
-		System::assert_last_event(
-			Event::LiquidityRemoved {
-				provider: account_id,
-				asset_id,
-				currency_amount: currency_amount_to_remove,
-				asset_amount: asset_amount_to_remove,
-				liquidity_amount: 10u128,
-			}
-			.into(),
+		//fails because the pool requires 10 assets for 10 currency
+		assert_noop!(
+			Dex::add_liquidity(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				9u128,
+				0u128,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+
+		//fails because only 10 liquidity assets are minted
+		assert_noop!(
+			Dex::add_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				10u128,
+				11u128,
+				None
+			),
+			Error::<Test>::SlippageExceeded
 		);
 	})
 }
 
 #[test]
-fn remove_liquidity_fails_liquidity_amount_zero() {
+fn add_liquidity_fails_pool_not_found() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
 		let account_id = 1u64;
@@ -378,16 +695,30 @@ fn remove_liquidity_fails_liquidity_amount_zero() {
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
 		//create an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//fails to remove liquidity because the liquidity asset amount is zero
-		assert_noop!(Dex::remove_liquidity(sender, asset_id, 0u128), Error::<Test>::LiqAmountZero);
+		//fails to add liquidity because the pool doesn't exists
+		assert_noop!(
+			Dex::add_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				u128::MAX,
+				0u128,
+				None
+			),
+			Error::<Test>::PoolNotFound
+		);
 	})
 }
 
 #[test]
-fn remove_liquidity_fails_pool_not_found() {
+fn add_liquidity_fails_currency_amount_zero() {
 	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
 		let account_id = 1u64;
@@ -395,22 +726,36 @@ fn remove_liquidity_fails_pool_not_found() {
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
 		//create an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//fails to remove liquidity because the pool with the requested asset_id is not created
-		assert_noop!(Dex::remove_liquidity(sender, asset_id, 10u128), Error::<Test>::PoolNotFound);
+		//fails to add liquidity because the currency amount is zero
+		assert_noop!(
+			Dex::add_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				0u128,
+				u128::MAX,
+				0u128,
+				None
+			),
+			Error::<Test>::CurrencyAmountZero
+		);
 	})
 }
 
 #[test]
-fn currency_to_asset_successfully() {
+fn remove_liquidity_successfully() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
 		let asset_id = 3u32;
+		let liquidity_asset_id = 1000u32;
 		let account_id = 1u64;
-		let liquidity_asset_id = 2u32;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
@@ -427,50 +772,67 @@ fn currency_to_asset_successfully() {
 		);
 
 		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128));
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
 
-		//get the pool created previously
-		let pool = Dex::pools(asset_id).unwrap_or_default();
+		//get the pool and reserves values before removing liquidity
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		let currency_reserve_before = pool.reserve_a.clone();
+		let asset_reserve_before = pool.reserve_b.clone();
 
-		//calculate the asset amount to give to the sender based on the currency input amount
-		let asset_amount_to_check = Dex::get_input_convert(
-			20u128,
-			pool.currency_reserve.clone(),
-			pool.asset_reserve.clone(),
-		)
-		.unwrap_or_default();
+		//remove liquidity
+		assert_ok!(Dex::remove_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			10u128,
+			10u128,
+			10u128,
+			None
+		));
 
-		//perform the currency to asset swap operation
-		assert_ok!(Dex::currency_to_asset(sender, 20u128, asset_id));
+		//check that the new currency amount to remove is (liquidity_amount * currency_reserve) /
+		// asset_liq_total_issuance
+		let currency_amount_to_remove = (10u128 * currency_reserve_before) / 50u128;
 
-		//verify new sender's asset balance
-		assert_eq!(
-			<Test as crate::Config>::Fungibles::balance(asset_id, &account_id),
-			50u128 + asset_amount_to_check
-		);
+		//check the new asset amount to remove is (liquidity_amount * asset_reserve) /
+		// asset_liq_total_issuance
+		let asset_amount_to_remove = (10u128 * asset_reserve_before) / 50u128;
 
-		//verify new sender's currency amount
-		assert_eq!(<Test as crate::Config>::Currency::free_balance(&account_id), 30u128);
+		//check the liq_assets were burnt
+		assert_eq!(<Test as crate::Config>::Fungibles::total_issuance(liquidity_asset_id), 40);
 
-		//verify new pallet's asset balance
+		//check the sender has new amount of currency and asset
 		assert_eq!(
-			<Test as crate::Config>::Fungibles::balance(asset_id, &Dex::account_id()),
-			50u128 - asset_amount_to_check
+			<Test as crate::Config>::Currency::free_balance(&account_id),
+			50u128 + currency_amount_to_remove
 		);
-
-		//verify new pallet's currency balance
 		assert_eq!(
-			<Test as crate::Config>::Currency::free_balance(&Dex::account_id()),
-			50u128 + 20u128
+			<Test as crate::Config>::Fungibles::balance(asset_id, &account_id),
+			50u128 + asset_amount_to_remove
 		);
 
+		//check updated values of the pool
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, currency_reserve_before - currency_amount_to_remove);
+		assert_eq!(pool.reserve_b, asset_reserve_before - asset_amount_to_remove);
+
 		//check the last event
 		System::assert_last_event(
-			Event::CurrencyToAsset {
-				sender: account_id,
-				asset_id,
-				currency_amount: 20u128,
-				asset_amount: asset_amount_to_check,
+			Event::LiquidityRemoved {
+				provider: account_id,
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				amount_a: currency_amount_to_remove,
+				amount_b: asset_amount_to_remove,
+				liquidity_amount: 10u128,
 			}
 			.into(),
 		);
@@ -478,70 +840,177 @@ fn currency_to_asset_successfully() {
 }
 
 #[test]
-fn currency_to_asset_fails_asset_not_found() {
+fn remove_liquidity_small_amount_rounds_down() {
 	new_test_ext().execute_with(|| {
-		System::set_block_number(1);
-
 		let asset_id = 3u32;
 		let account_id = 1u64;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
-		//fails to swap currency for an non-existent asset
-		assert_noop!(
-			Dex::currency_to_asset(sender, 20u128, asset_id),
-			Error::<Test>::AssetNotFound
-		);
-	})
-}
-
-#[test]
-fn currency_to_asset_fails_pool_not_found() {
-	new_test_ext().execute_with(|| {
-		System::set_block_number(1);
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 2_000u128);
 
-		let asset_id = 3u32;
-		let account_id = 1u64;
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			7u128,
+			None
+		));
+
+		//remove a tenth of the liquidity: floor(100 * 7 / 1000) = 0 assets are withdrawn
+		assert_ok!(Dex::remove_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			100u128,
+			0u128,
+			0u128,
+			None
+		));
+
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 900u128);
+		assert_eq!(pool.reserve_b, 7u128);
+	})
+}
+
+#[test]
+fn remove_liquidity_fails_slippage_exceeded() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let account_id = 1u64;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
 		//create an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//fails to swap currency because the pool does not exist
-		assert_noop!(Dex::currency_to_asset(sender, 10u128, asset_id), Error::<Test>::PoolNotFound);
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//fails because only 10 currency would be withdrawn
+		assert_noop!(
+			Dex::remove_liquidity(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				11u128,
+				0u128,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+
+		//fails because only 10 assets would be withdrawn
+		assert_noop!(
+			Dex::remove_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				0u128,
+				11u128,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
 	})
 }
 
 #[test]
-fn currency_to_asset_fails_currency_amount_zero() {
+fn remove_liquidity_fails_liquidity_amount_zero() {
 	new_test_ext().execute_with(|| {
-		System::set_block_number(1);
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//fails to remove liquidity because the liquidity asset amount is zero
+		assert_noop!(
+			Dex::remove_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				0u128,
+				0u128,
+				0u128,
+				None
+			),
+			Error::<Test>::LiqAmountZero
+		);
+	})
+}
 
+#[test]
+fn remove_liquidity_fails_pool_not_found() {
+	new_test_ext().execute_with(|| {
 		let asset_id = 3u32;
 		let account_id = 1u64;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
-		//fails to swap currency for a currency amount of zero
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//fails to remove liquidity because the pool with the requested asset_id is not created
 		assert_noop!(
-			Dex::currency_to_asset(sender, 0u128, asset_id),
-			Error::<Test>::CurrencyAmountZero
+			Dex::remove_liquidity(
+				sender,
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				0u128,
+				0u128,
+				None
+			),
+			Error::<Test>::PoolNotFound
 		);
 	})
 }
 
 #[test]
-fn asset_to_currency_successfully() {
+fn currency_to_asset_successfully() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
 		let asset_id = 3u32;
 		let account_id = 1u64;
-		let liquidity_asset_id = 2u32;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
@@ -558,53 +1027,55 @@ fn asset_to_currency_successfully() {
 		);
 
 		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender.clone(), asset_id, liquidity_asset_id, 50u128, 50u128));
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
 
 		//get the pool created previously
-		let pool = Dex::pools(asset_id).unwrap_or_default();
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
 
-		//calculate the asset amount to give to the sender based on the asset input amount
-		let curency_amount_to_check = Dex::get_input_convert(
-			20u128,
-			pool.asset_reserve.clone(),
-			pool.currency_reserve.clone(),
-		)
-		.unwrap_or_default();
+		//calculate the asset amount to give to the sender based on the currency input amount
+		let asset_amount_to_check =
+			Dex::get_input_convert(20u128, pool.reserve_a.clone(), pool.reserve_b.clone(), FEE)
+				.unwrap_or_default();
 
-		//perform the asset to currency swap operation
-		assert_ok!(Dex::asset_to_currency(sender.clone(), 20u128, asset_id));
+		//perform the currency to asset swap operation
+		assert_ok!(Dex::currency_to_asset(sender, 20u128, asset_id, asset_amount_to_check, None));
 
 		//verify new sender's asset balance
 		assert_eq!(
 			<Test as crate::Config>::Fungibles::balance(asset_id, &account_id),
-			50u128 - 20u128
+			50u128 + asset_amount_to_check
 		);
 
 		//verify new sender's currency amount
-		assert_eq!(
-			<Test as crate::Config>::Currency::free_balance(&account_id),
-			50u128 + curency_amount_to_check
-		);
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&account_id), 30u128);
 
 		//verify new pallet's asset balance
 		assert_eq!(
 			<Test as crate::Config>::Fungibles::balance(asset_id, &Dex::account_id()),
-			50u128 + 20u128
+			50u128 - asset_amount_to_check
 		);
 
 		//verify new pallet's currency balance
 		assert_eq!(
 			<Test as crate::Config>::Currency::free_balance(&Dex::account_id()),
-			50u128 - curency_amount_to_check
+			50u128 + 20u128
 		);
 
 		//check the last event
 		System::assert_last_event(
-			Event::AssetToCurrency {
+			Event::CurrencyToAsset {
 				sender: account_id,
 				asset_id,
-				asset_amount: 20u128,
-				currency_amount: curency_amount_to_check,
+				currency_amount: 20u128,
+				asset_amount: asset_amount_to_check,
 			}
 			.into(),
 		);
@@ -612,7 +1083,53 @@ fn asset_to_currency_successfully() {
 }
 
 #[test]
-fn asset_to_currency_fails_asset_not_found() {
+fn currency_to_asset_fails_slippage_exceeded() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//get the pool created previously
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+
+		//quote the swap
+		let asset_amount_to_check =
+			Dex::get_input_convert(20u128, pool.reserve_a, pool.reserve_b, FEE).unwrap_or_default();
+
+		//fails because the sender asks for more than the quoted amount
+		assert_noop!(
+			Dex::currency_to_asset(sender, 20u128, asset_id, asset_amount_to_check + 1, None),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn currency_to_asset_fails_asset_not_found() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
@@ -622,16 +1139,16 @@ fn asset_to_currency_fails_asset_not_found() {
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
-		//fails to swap asset because the asset does not exist
+		//fails to swap currency for an non-existent asset
 		assert_noop!(
-			Dex::asset_to_currency(sender, 20u128, asset_id),
+			Dex::currency_to_asset(sender, 20u128, asset_id, 0u128, None),
 			Error::<Test>::AssetNotFound
 		);
 	})
 }
 
 #[test]
-fn asset_to_currency_fails_pool_not_found() {
+fn currency_to_asset_fails_pool_not_found() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
@@ -644,13 +1161,16 @@ fn asset_to_currency_fails_pool_not_found() {
 		//create an asset
 		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//fails to swap asset because pool does not exist
-		assert_noop!(Dex::asset_to_currency(sender, 10u128, asset_id), Error::<Test>::PoolNotFound);
+		//fails to swap currency because the pool does not exist
+		assert_noop!(
+			Dex::currency_to_asset(sender, 10u128, asset_id, 0u128, None),
+			Error::<Test>::PoolNotFound
+		);
 	})
 }
 
 #[test]
-fn asset_to_currency_fails_asset_amount_zero() {
+fn currency_to_asset_fails_currency_amount_zero() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
@@ -660,112 +1180,95 @@ fn asset_to_currency_fails_asset_amount_zero() {
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
-		//fails to swap asset for an asset amount of zero
+		//fails to swap currency for a currency amount of zero
 		assert_noop!(
-			Dex::asset_to_currency(sender, 0u128, asset_id),
-			Error::<Test>::AssetAmountZero
+			Dex::currency_to_asset(sender, 0u128, asset_id, 0u128, None),
+			Error::<Test>::CurrencyAmountZero
 		);
 	})
 }
 
 #[test]
-fn asset_to_asset_successfully() {
+fn asset_to_currency_successfully() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
-		let asset_id_from = 3u32;
-		let asset_id_to = 5u32;
+		let asset_id = 3u32;
 		let account_id = 1u64;
-		let liquidity_asset_id_from = 2u32;
-		let liquidity_asset_id_to = 4u32;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
 		//transfer currency to the sender
-		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
-
-		//create the asset_from
-		assert_ok!(Dex::create_asset_helper(asset_id_from));
-
-		//create the asset_to
-		assert_ok!(Dex::create_asset_helper(asset_id_to));
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
 
-		//mint asset_from to user
-		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_from, &account_id, 300u128)
-			.is_ok());
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
 
-		//mint asset_to to user
-		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_to, &account_id, 300u128)
-			.is_ok());
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
 
-		//create pool_from
+		//create a pool and add liquidity to it
 		assert_ok!(Dex::create_pool(
 			sender.clone(),
-			asset_id_from,
-			liquidity_asset_id_from,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
 			50u128,
-			50u128
-		));
-
-		//create pool_to
-		assert_ok!(Dex::create_pool(
-			sender.clone(),
-			asset_id_to,
-			liquidity_asset_id_to,
 			50u128,
-			50u128
+			None
 		));
 
-		//get the pool_from
-		let pool_from = Dex::pools(asset_id_from).unwrap_or_default();
+		//get the pool created previously
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
 
-		//get the pool_to
-		let pool_to = Dex::pools(asset_id_to).unwrap_or_default();
+		//calculate the asset amount to give to the sender based on the asset input amount
+		let curency_amount_to_check =
+			Dex::get_input_convert(20u128, pool.reserve_b.clone(), pool.reserve_a.clone(), FEE)
+				.unwrap_or_default();
 
-		//middle calculation
-		let curency_amount_middle = Dex::get_input_convert(
+		//perform the asset to currency swap operation
+		assert_ok!(Dex::asset_to_currency(
+			sender.clone(),
 			20u128,
-			pool_from.asset_reserve.clone(),
-			pool_from.currency_reserve.clone(),
-		)
-		.unwrap_or_default();
-
-		let asset_final_amount = Dex::get_input_convert(
-			curency_amount_middle,
-			pool_to.currency_reserve.clone(),
-			pool_to.asset_reserve.clone(),
-		)
-		.unwrap_or_default();
+			asset_id,
+			curency_amount_to_check,
+			None
+		));
 
-		let previous_balance_from =
-			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id);
-		let previous_balance_to =
-			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id);
+		//verify new sender's asset balance
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id, &account_id),
+			50u128 - 20u128
+		);
 
-		//perform the asset to asset swap operation
-		assert_ok!(Dex::asset_to_asset(sender.clone(), asset_id_from, asset_id_to, 20u128));
+		//verify new sender's currency amount
+		assert_eq!(
+			<Test as crate::Config>::Currency::free_balance(&account_id),
+			50u128 + curency_amount_to_check
+		);
 
-		//verify new sender's asset_from balance
+		//verify new pallet's asset balance
 		assert_eq!(
-			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id),
-			previous_balance_from - 20u128
+			<Test as crate::Config>::Fungibles::balance(asset_id, &Dex::account_id()),
+			50u128 + 20u128
 		);
 
-		//verify new sender's asset_to balance
+		//verify new pallet's currency balance
 		assert_eq!(
-			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id),
-			previous_balance_to + asset_final_amount
+			<Test as crate::Config>::Currency::free_balance(&Dex::account_id()),
+			50u128 - curency_amount_to_check
 		);
 
 		//check the last event
 		System::assert_last_event(
-			Event::AssetToAsset {
+			Event::AssetToCurrency {
 				sender: account_id,
-				asset_id_from,
-				asset_id_to,
+				asset_id,
 				asset_amount: 20u128,
-				asset_amount_received: asset_final_amount,
+				currency_amount: curency_amount_to_check,
 			}
 			.into(),
 		);
@@ -773,29 +1276,252 @@ fn asset_to_asset_successfully() {
 }
 
 #[test]
-fn asset_to_asset_fails_asset_from_not_found() {
+fn asset_to_currency_fails_slippage_exceeded() {
 	new_test_ext().execute_with(|| {
-		System::set_block_number(1);
-
-		let asset_id_from = 3u32;
-		let asset_id_to = 5u32;
+		let asset_id = 3u32;
 		let account_id = 1u64;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
-		//fails to swap asset for an asset because asset_id_from does not exist
-		assert_noop!(
-			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128),
-			Error::<Test>::AssetNotFound
-		);
-	})
-}
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
 
-#[test]
-fn asset_to_asset_fails_asset_to_not_found() {
-	new_test_ext().execute_with(|| {
-		System::set_block_number(1);
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//get the pool created previously
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+
+		//quote the swap
+		let currency_amount_to_check =
+			Dex::get_input_convert(20u128, pool.reserve_b, pool.reserve_a, FEE).unwrap_or_default();
+
+		//fails because the sender asks for more than the quoted amount
+		assert_noop!(
+			Dex::asset_to_currency(sender, 20u128, asset_id, currency_amount_to_check + 1, None),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn asset_to_currency_fails_asset_not_found() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//fails to swap asset because the asset does not exist
+		assert_noop!(
+			Dex::asset_to_currency(sender, 20u128, asset_id, 0u128, None),
+			Error::<Test>::AssetNotFound
+		);
+	})
+}
+
+#[test]
+fn asset_to_currency_fails_pool_not_found() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//fails to swap asset because pool does not exist
+		assert_noop!(
+			Dex::asset_to_currency(sender, 10u128, asset_id, 0u128, None),
+			Error::<Test>::PoolNotFound
+		);
+	})
+}
+
+#[test]
+fn asset_to_currency_fails_asset_amount_zero() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//fails to swap asset for an asset amount of zero
+		assert_noop!(
+			Dex::asset_to_currency(sender, 0u128, asset_id, 0u128, None),
+			Error::<Test>::AssetAmountZero
+		);
+	})
+}
+
+#[test]
+fn asset_to_asset_successfully() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id_from = 3u32;
+		let asset_id_to = 5u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+
+		//create the asset_from
+		assert_ok!(Dex::create_asset_helper(asset_id_from));
+
+		//create the asset_to
+		assert_ok!(Dex::create_asset_helper(asset_id_to));
+
+		//mint asset_from to user
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_from, &account_id, 300u128)
+			.is_ok());
+
+		//mint asset_to to user
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_to, &account_id, 300u128)
+			.is_ok());
+
+		//create pool_from
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_from),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//create pool_to
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_to),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//get the pool_from
+		let pool_from =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id_from))).unwrap_or_default();
+
+		//get the pool_to
+		let pool_to =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id_to))).unwrap_or_default();
+
+		//middle calculation
+		let curency_amount_middle = Dex::get_input_convert(
+			20u128,
+			pool_from.reserve_b.clone(),
+			pool_from.reserve_a.clone(),
+			FEE,
+		)
+		.unwrap_or_default();
+
+		let asset_final_amount = Dex::get_input_convert(
+			curency_amount_middle,
+			pool_to.reserve_a.clone(),
+			pool_to.reserve_b.clone(),
+			FEE,
+		)
+		.unwrap_or_default();
+
+		let previous_balance_from =
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id);
+		let previous_balance_to =
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id);
+
+		//perform the asset to asset swap operation
+		assert_ok!(Dex::asset_to_asset(
+			sender.clone(),
+			asset_id_from,
+			asset_id_to,
+			20u128,
+			asset_final_amount,
+			None
+		));
+
+		//verify new sender's asset_from balance
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id),
+			previous_balance_from - 20u128
+		);
+
+		//verify new sender's asset_to balance
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id),
+			previous_balance_to + asset_final_amount
+		);
+
+		//check the last event
+		System::assert_last_event(
+			Event::AssetToAsset {
+				sender: account_id,
+				asset_id_from,
+				asset_id_to,
+				asset_amount: 20u128,
+				asset_amount_received: asset_final_amount,
+			}
+			.into(),
+		);
+	})
+}
+
+#[test]
+fn asset_to_asset_fails_asset_from_not_found() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id_from = 3u32;
+		let asset_id_to = 5u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//fails to swap asset for an asset because asset_id_from does not exist
+		assert_noop!(
+			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128, 0u128, None),
+			Error::<Test>::AssetNotFound
+		);
+	})
+}
+
+#[test]
+fn asset_to_asset_fails_asset_to_not_found() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
 
 		let asset_id_from = 3u32;
 		let asset_id_to = 5u32;
@@ -809,7 +1535,7 @@ fn asset_to_asset_fails_asset_to_not_found() {
 
 		//fails to swap asset for an asset because asset_id_to does not exist
 		assert_noop!(
-			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128),
+			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128, 0u128, None),
 			Error::<Test>::AssetNotFound
 		);
 	})
@@ -835,7 +1561,7 @@ fn asset_to_asset_fails_pool_from_not_found() {
 
 		//fails to swap asset for an asset because pool_from does not exist
 		assert_noop!(
-			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128),
+			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128, 0u128, None),
 			Error::<Test>::PoolNotFound
 		);
 	})
@@ -865,19 +1591,21 @@ fn asset_to_asset_fails_pool_to_not_found() {
 		//mint asset_from to sender
 		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_from, &account_id, 300u128)
 			.is_ok());
-		
+
 		//create pool_from
 		assert_ok!(Dex::create_pool(
 			sender.clone(),
-			asset_id_from,
-			8u32,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_from),
+			Some(FEE),
+			50u128,
 			50u128,
-			50u128
+			None
 		));
 
 		//fails to swap asset for an asset because pool_to does not exist
 		assert_noop!(
-			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128),
+			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 19u128, 0u128, None),
 			Error::<Test>::PoolNotFound
 		);
 	})
@@ -897,45 +1625,3409 @@ fn asset_to_asset_fails_asset_amount_zero() {
 
 		//fails to swap asset for an asset because asset_amount is zero
 		assert_noop!(
-			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 0u128),
+			Dex::asset_to_asset(sender, asset_id_from, asset_id_to, 0u128, 0u128, None),
 			Error::<Test>::AssetAmountZero
 		);
 	})
 }
 
 #[test]
-fn price_oracle_successfully() {
+fn asset_to_asset_uses_direct_pool() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 
-		let asset_id = 3u32;
-		let liquidity_asset_id = 2u32;
+		let asset_id_from = 3u32;
+		let asset_id_to = 5u32;
 		let account_id = 1u64;
+		let liquidity_asset_id = 1000u32;
 
 		//create a sender
 		let sender = RuntimeOrigin::signed(account_id);
 
-		//transfer currency to the sender
+		//transfer currency to the sender and to the pallet account, so both can hold assets
 		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 1u128);
 
-		//create an asset
-		assert_ok!(Dex::create_asset_helper(asset_id));
+		//create both assets
+		assert_ok!(Dex::create_asset_helper(asset_id_from));
+		assert_ok!(Dex::create_asset_helper(asset_id_to));
 
-		//mint asset to user
-		assert!(
-			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		//mint both assets to user
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_from, &account_id, 300u128)
+			.is_ok());
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_to, &account_id, 300u128)
+			.is_ok());
+
+		//create an asset/asset pool, giving the sides in reverse order
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Asset(asset_id_to),
+			AssetKind::Asset(asset_id_from),
+			Some(FEE),
+			50u128,
+			40u128,
+			None
+		));
+
+		//the pool sides and reserves are stored ordered
+		let pool_id = (AssetKind::Asset(asset_id_from), AssetKind::Asset(asset_id_to));
+		assert_eq!(
+			Dex::pools(pool_id.clone()).unwrap_or_default(),
+			Pool {
+				asset_a: AssetKind::Asset(asset_id_from),
+				asset_b: AssetKind::Asset(asset_id_to),
+				reserve_a: 40u128,
+				reserve_b: 50u128,
+				liquidity_asset_id,
+				fee: FEE,
+				status: PoolStatus::Active,
+				..Default::default()
+			}
 		);
 
-		//create a pool and add liquidity to it
-		assert_ok!(Dex::create_pool(sender, asset_id, liquidity_asset_id, 50u128, 50u128));
+		//the swap only pays the fee of the direct pool
+		let asset_final_amount = Dex::get_input_convert(20u128, 40u128, 50u128, FEE).unwrap();
+		assert_ok!(Dex::asset_to_asset(
+			sender,
+			asset_id_from,
+			asset_id_to,
+			20u128,
+			asset_final_amount,
+			None
+		));
 
-		let expected_oracle = OraclePrice {
-			asset_id,
-			asset_amount: 1,
-			currency_amount: 1
-		};
+		//verify the reserves of the pool
+		let pool = Dex::pools(pool_id).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 60u128);
+		assert_eq!(pool.reserve_b, 50u128 - asset_final_amount);
 
-		//verify the oracle's behaviour is correct
-		assert_eq!(Dex::price_oracle(asset_id).unwrap_or_default(), expected_oracle);
+		//verify new sender's balances
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id),
+			300u128 - 40u128 - 20u128
+		);
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id),
+			300u128 - 50u128 + asset_final_amount
+		);
+	})
+}
+
+#[test]
+fn create_pool_fails_identical_assets() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//fails to create a pool with the same asset on both sides
+		assert_noop!(
+			Dex::create_pool(
+				sender,
+				AssetKind::Asset(asset_id),
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				50u128,
+				50u128,
+				None
+			),
+			Error::<Test>::IdenticalAssets
+		);
+	})
+}
+
+#[test]
+fn currency_to_exact_asset_successfully() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//(1000 * 50 * 10) / (997 * 40) = 12.53, rounded up
+		let currency_amount_to_check = 13u128;
+
+		//perform the currency to exact asset swap operation
+		assert_ok!(Dex::currency_to_exact_asset(
+			sender,
+			10u128,
+			asset_id,
+			currency_amount_to_check,
+			None
+		));
+
+		//verify new sender's balances
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &account_id), 60u128);
+		assert_eq!(
+			<Test as crate::Config>::Currency::free_balance(&account_id),
+			50u128 - currency_amount_to_check
+		);
+
+		//verify the pool reserves
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 50u128 + currency_amount_to_check);
+		assert_eq!(pool.reserve_b, 40u128);
+
+		//check the last event
+		System::assert_last_event(
+			Event::CurrencyToExactAsset {
+				sender: account_id,
+				asset_id,
+				currency_amount: currency_amount_to_check,
+				asset_amount: 10u128,
+			}
+			.into(),
+		);
+	})
+}
+
+#[test]
+fn asset_to_exact_currency_fails_slippage_exceeded() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//get the pool created previously
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+
+		//quote the swap
+		let asset_amount_to_check =
+			Dex::get_output_convert(10u128, pool.reserve_b, pool.reserve_a, FEE)
+				.unwrap_or_default();
+
+		//fails because the sender accepts to pay less than the quoted amount
+		assert_noop!(
+			Dex::asset_to_exact_currency(sender, 10u128, asset_id, asset_amount_to_check - 1, None),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn asset_to_exact_asset_successfully() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id_from = 3u32;
+		let asset_id_to = 5u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+
+		//create the asset_from
+		assert_ok!(Dex::create_asset_helper(asset_id_from));
+
+		//create the asset_to
+		assert_ok!(Dex::create_asset_helper(asset_id_to));
+
+		//mint asset_from to user
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_from, &account_id, 300u128)
+			.is_ok());
+
+		//mint asset_to to user
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_to, &account_id, 300u128)
+			.is_ok());
+
+		//create pool_from
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_from),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//create pool_to
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_to),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//middle calculation
+		let currency_amount_middle = Dex::get_output_convert(10u128, 50u128, 50u128, FEE).unwrap();
+		let asset_amount_in =
+			Dex::get_output_convert(currency_amount_middle, 50u128, 50u128, FEE).unwrap();
+
+		let previous_balance_from =
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id);
+		let previous_balance_to =
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id);
+
+		//fails when the maximum input is below the quote
+		assert_noop!(
+			Dex::asset_to_exact_asset(
+				sender.clone(),
+				asset_id_from,
+				asset_id_to,
+				10u128,
+				asset_amount_in - 1,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+
+		//perform the asset to exact asset swap operation
+		assert_ok!(Dex::asset_to_exact_asset(
+			sender,
+			asset_id_from,
+			asset_id_to,
+			10u128,
+			asset_amount_in,
+			None
+		));
+
+		//verify new sender's balances
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id),
+			previous_balance_from - asset_amount_in
+		);
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id),
+			previous_balance_to + 10u128
+		);
+
+		//verify the reserves of both pools
+		let pool_from =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id_from))).unwrap_or_default();
+		let pool_to =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id_to))).unwrap_or_default();
+		assert_eq!(pool_from.reserve_b, 50u128 + asset_amount_in);
+		assert_eq!(pool_from.reserve_a, 50u128 - currency_amount_middle);
+		assert_eq!(pool_to.reserve_b, 40u128);
+		assert_eq!(pool_to.reserve_a, 50u128 + currency_amount_middle);
+
+		//check the last event
+		System::assert_last_event(
+			Event::AssetToExactAsset {
+				sender: account_id,
+				asset_id_from,
+				asset_id_to,
+				asset_amount: asset_amount_in,
+				asset_amount_received: 10u128,
+			}
+			.into(),
+		);
+	})
+}
+
+#[test]
+fn get_output_convert_is_the_inverse_of_get_input_convert() {
+	new_test_ext().execute_with(|| {
+		//the quoted input always buys at least the requested output
+		for output_amount in [1u128, 10u128, 250u128, 999u128] {
+			let input_amount =
+				Dex::get_output_convert(output_amount, 1000u128, 1000u128, FEE).unwrap();
+			assert!(
+				Dex::get_input_convert(input_amount, 1000u128, 1000u128, FEE).unwrap() >=
+					output_amount
+			);
+			assert!(
+				Dex::get_input_convert(input_amount - 1, 1000u128, 1000u128, FEE).unwrap() <
+					output_amount
+			);
+		}
+
+		//the whole reserve cannot be bought
+		assert_eq!(
+			Dex::get_output_convert(1000u128, 1000u128, 1000u128, FEE),
+			Err(Error::<Test>::InsufficientPoolReserve)
+		);
+	})
+}
+
+#[test]
+fn swap_exact_in_along_path_successfully() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id_from = 3u32;
+		let asset_id_to = 5u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+
+		//create the assets and mint them to the user
+		assert_ok!(Dex::create_asset_helper(asset_id_from));
+		assert_ok!(Dex::create_asset_helper(asset_id_to));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_from, &account_id, 300u128)
+			.is_ok());
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id_to, &account_id, 300u128)
+			.is_ok());
+
+		//create both currency pools
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_from),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id_to),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		let path =
+			vec![AssetKind::Asset(asset_id_from), AssetKind::Native, AssetKind::Asset(asset_id_to)];
+		let currency_amount_middle = Dex::get_input_convert(10u128, 50u128, 50u128, FEE).unwrap();
+		let asset_amount_out =
+			Dex::get_input_convert(currency_amount_middle, 50u128, 50u128, FEE).unwrap();
+
+		//fails when the final output is below the minimum
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				sender.clone(),
+				path.clone().try_into().unwrap(),
+				10u128,
+				asset_amount_out + 1,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+
+		let previous_balance_from =
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id);
+		let previous_balance_to =
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id);
+
+		assert_ok!(Dex::swap_exact_in_along_path(
+			sender,
+			path.clone().try_into().unwrap(),
+			10u128,
+			asset_amount_out,
+			None
+		));
+
+		//verify new sender's balances
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_from, &account_id),
+			previous_balance_from - 10u128
+		);
+		assert_eq!(
+			<Test as crate::Config>::Fungibles::balance(asset_id_to, &account_id),
+			previous_balance_to + asset_amount_out
+		);
+
+		//verify the reserves of both pools
+		let pool_from =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id_from))).unwrap_or_default();
+		let pool_to =
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id_to))).unwrap_or_default();
+		assert_eq!(pool_from.reserve_b, 60u128);
+		assert_eq!(pool_from.reserve_a, 50u128 - currency_amount_middle);
+		assert_eq!(pool_to.reserve_a, 50u128 + currency_amount_middle);
+		assert_eq!(pool_to.reserve_b, 50u128 - asset_amount_out);
+
+		//check the last event
+		System::assert_last_event(
+			Event::SwapExecuted {
+				sender: account_id,
+				path,
+				amounts: vec![10u128, currency_amount_middle, asset_amount_out],
+			}
+			.into(),
+		);
+	})
+}
+
+#[test]
+fn swap_exact_out_along_path_fails_slippage_exceeded() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+
+		//create the asset and mint it to the user
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		//create the pool
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		let currency_amount_in = Dex::get_output_convert(10u128, 50u128, 50u128, FEE).unwrap();
+
+		assert_noop!(
+			Dex::swap_exact_out_along_path(
+				sender.clone(),
+				vec![AssetKind::Native, AssetKind::Asset(asset_id)].try_into().unwrap(),
+				10u128,
+				currency_amount_in - 1,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+
+		assert_ok!(Dex::swap_exact_out_along_path(
+			sender,
+			vec![AssetKind::Native, AssetKind::Asset(asset_id)].try_into().unwrap(),
+			10u128,
+			currency_amount_in,
+			None
+		));
+
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 50u128 + currency_amount_in);
+		assert_eq!(pool.reserve_b, 40u128);
+	})
+}
+
+#[test]
+fn swap_along_path_fails_invalid_path() {
+	new_test_ext().execute_with(|| {
+		let sender = RuntimeOrigin::signed(1u64);
+
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				sender.clone(),
+				vec![AssetKind::Native].try_into().unwrap(),
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+
+		//the path cannot be longer than MaxHops
+		let too_long: Result<frame_support::BoundedVec<_, <Test as crate::Config>::MaxHops>, _> =
+			vec![AssetKind::Native; 5].try_into();
+		assert!(too_long.is_err());
+	})
+}
+
+#[test]
+fn swap_along_path_fails_when_a_pool_is_revisited() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+
+		//create the currency pools of assets 3 and 5, and the pool between both assets
+		for asset_id in [3u32, 5u32] {
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128)
+				.is_ok());
+			assert_ok!(Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				50u128,
+				50u128,
+				None
+			));
+		}
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Asset(3u32),
+			AssetKind::Asset(5u32),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		//going back and forth through the same pool would price the second leg on the reserves
+		//before the first one
+		let there_and_back = vec![AssetKind::Native, AssetKind::Asset(3u32), AssetKind::Native];
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				sender.clone(),
+				there_and_back.clone().try_into().unwrap(),
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+		assert_noop!(
+			Dex::swap_exact_out_along_path(
+				sender.clone(),
+				there_and_back.clone().try_into().unwrap(),
+				5u128,
+				100u128,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+		assert_eq!(Dex::quote(10u128, &there_and_back), Err(Error::<Test>::InvalidPath.into()));
+
+		//an asset in the middle of the path cannot be visited twice either
+		let repeated = vec![
+			AssetKind::Asset(3u32),
+			AssetKind::Native,
+			AssetKind::Asset(5u32),
+			AssetKind::Native,
+		];
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				sender.clone(),
+				repeated.try_into().unwrap(),
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+
+		//a loop through three different pools back to the first asset is allowed
+		let cycle = vec![
+			AssetKind::Native,
+			AssetKind::Asset(3u32),
+			AssetKind::Asset(5u32),
+			AssetKind::Native,
+		];
+		assert_ok!(Dex::swap_exact_in_along_path(
+			sender,
+			cycle.try_into().unwrap(),
+			10u128,
+			0u128,
+			None
+		));
+	})
+}
+
+#[test]
+#[allow(deprecated)]
+fn price_oracle_successfully() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool and add liquidity to it
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			None
+		));
+
+		let expected_oracle = OraclePrice { asset_id, asset_amount: 1, currency_amount: 1 };
+
+		//verify the oracle's behaviour is correct
+		assert_eq!(Dex::price_oracle(asset_id).unwrap_or_default(), expected_oracle);
+	})
+}
+
+#[test]
+fn currency_to_asset_fails_deadline_expired() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create a sender
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//transfer currency to the sender
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 100u128);
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//mint asset to user
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 100u128).is_ok()
+		);
+
+		//create a pool with a deadline on the current block
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			50u128,
+			50u128,
+			Some(10)
+		));
+
+		//fails to swap because the deadline was the previous block
+		assert_noop!(
+			Dex::currency_to_asset(sender.clone(), 20u128, asset_id, 0u128, Some(9)),
+			Error::<Test>::DeadlineExpired
+		);
+
+		//succeeds with a deadline in the future
+		assert_ok!(Dex::currency_to_asset(sender, 20u128, asset_id, 0u128, Some(11)));
+	})
+}
+
+#[test]
+fn mint_asset_fails_deadline_expired() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+
+		let asset_id = 3u32;
+		let account_id = 1u64;
+
+		//create an asset
+		assert_ok!(Dex::create_asset_helper(asset_id));
+
+		//fails to mint because the deadline was the previous block
+		assert_noop!(
+			Dex::mint_asset(RuntimeOrigin::signed(account_id), asset_id, 100u128, Some(9)),
+			Error::<Test>::DeadlineExpired
+		);
+
+		//succeeds on the deadline block
+		assert_ok!(Dex::mint_asset(RuntimeOrigin::signed(account_id), asset_id, 100u128, Some(10)));
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &account_id), 100u128);
+	})
+}
+
+#[test]
+fn check_deadline_rejects_expired_calls() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+
+		let account_id = 1u64;
+		let info = Default::default();
+
+		let call_with_deadline = |deadline| {
+			RuntimeCall::Dex(crate::Call::currency_to_asset {
+				currency_amount: 20u128,
+				asset_id: 3u32,
+				min_asset_out: 0u128,
+				deadline,
+			})
+		};
+
+		//calls without deadline are always valid
+		assert!(CheckDeadline::<Test>::new()
+			.validate(&account_id, &call_with_deadline(None), &info, 0)
+			.is_ok());
+
+		//calls before their deadline are valid until the deadline block
+		let valid = CheckDeadline::<Test>::new()
+			.validate(&account_id, &call_with_deadline(Some(12)), &info, 0)
+			.unwrap();
+		assert_eq!(valid.longevity, 3);
+
+		//expired calls are rejected before entering the pool
+		assert_eq!(
+			CheckDeadline::<Test>::new().validate(
+				&account_id,
+				&call_with_deadline(Some(9)),
+				&info,
+				0
+			),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Stale))
+		);
+
+		//flash swaps expire like every other dex call
+		let flash_swap = |deadline| {
+			RuntimeCall::Dex(crate::Call::flash_swap {
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(3u32),
+				amount_a_out: 0u128,
+				amount_b_out: 10u128,
+				data: vec![],
+				deadline,
+			})
+		};
+		assert_eq!(
+			CheckDeadline::<Test>::new().validate(&account_id, &flash_swap(Some(9)), &info, 0),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Stale))
+		);
+		let valid = CheckDeadline::<Test>::new()
+			.validate(&account_id, &flash_swap(Some(12)), &info, 0)
+			.unwrap();
+		assert_eq!(valid.longevity, 3);
+
+		//calls from other pallets are not affected
+		let other_call = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		assert!(CheckDeadline::<Test>::new()
+			.validate(&account_id, &other_call, &info, 0)
+			.is_ok());
+	})
+}
+
+#[test]
+fn migration_to_v1_moves_currency_pools_to_the_final_layout() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let liquidity_asset_id = 2u32;
+
+		//store a pool with the layout used before version 1
+		StorageVersion::new(0).put::<Dex>();
+		migrations::v1::PoolsMap::<Test>::insert(
+			asset_id,
+			migrations::v1::OldPool {
+				asset_id,
+				currency_reserve: 50u128,
+				asset_reserve: 40u128,
+				liquidity_asset_id,
+			},
+		);
+
+		migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+		//the pool is keyed by the (currency, asset) pair, is active, keeps charging the 0.3%
+		//fee and starts its cumulative prices at zero
+		assert_eq!(
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))),
+			Some(Pool {
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				reserve_a: 50u128,
+				reserve_b: 40u128,
+				liquidity_asset_id,
+				fee: Permill::from_perthousand(3),
+				status: PoolStatus::Active,
+				price_a_cumulative: FixedU128::from_inner(0),
+				price_b_cumulative: FixedU128::from_inner(0),
+				last_timestamp: 0,
+			})
+		);
+		assert_eq!(PoolsMap::<Test>::iter().count(), 1);
+		assert_eq!(StorageVersion::get::<Dex>(), StorageVersion::new(1));
+
+		//running it again does nothing
+		migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_eq!(PoolsMap::<Test>::iter().count(), 1);
+	})
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migration_to_v1_checks_the_pool_count_and_reserves() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Dex>();
+		for asset_id in [1u32, 3u32] {
+			migrations::v1::PoolsMap::<Test>::insert(
+				asset_id,
+				migrations::v1::OldPool {
+					asset_id,
+					currency_reserve: 50u128,
+					asset_reserve: 40u128,
+					liquidity_asset_id: asset_id + 1000,
+				},
+			);
+		}
+
+		let state = migrations::v1::MigrateToV1::<Test>::pre_upgrade().unwrap();
+		migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_ok!(migrations::v1::MigrateToV1::<Test>::post_upgrade(state.clone()));
+
+		//a pool whose reserves changed during the upgrade is reported
+		PoolsMap::<Test>::mutate((AssetKind::Native, AssetKind::Asset(1u32)), |pool| {
+			pool.as_mut().unwrap().reserve_a = 49u128
+		});
+		assert!(migrations::v1::MigrateToV1::<Test>::post_upgrade(state.clone()).is_err());
+
+		//and so is a lost pool
+		PoolsMap::<Test>::remove((AssetKind::Native, AssetKind::Asset(1u32)));
+		assert!(migrations::v1::MigrateToV1::<Test>::post_upgrade(state).is_err());
+	})
+}
+
+#[test]
+fn create_pool_fails_fee_tier_not_allowed() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		//0.2% is not one of the approved tiers
+		assert_noop!(
+			Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(Permill::from_parts(2_000)),
+				50u128,
+				50u128,
+				None
+			),
+			Error::<Test>::FeeTierNotAllowed
+		);
+
+		//it can be used once governance approves it
+		assert_ok!(Dex::set_fee_tiers(
+			RuntimeOrigin::root(),
+			vec![Permill::from_parts(2_000)].try_into().unwrap()
+		));
+		assert_eq!(Dex::fee_tiers().into_inner(), vec![Permill::from_parts(2_000)]);
+
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(Permill::from_parts(2_000)),
+			50u128,
+			50u128,
+			None
+		));
+		assert_eq!(
+			Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id)))
+				.unwrap_or_default()
+				.fee,
+			Permill::from_parts(2_000)
+		);
+	})
+}
+
+#[test]
+fn set_fee_tiers_fails_bad_origin_or_invalid_fee() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Dex::set_fee_tiers(
+				RuntimeOrigin::signed(1u64),
+				vec![Permill::from_parts(2_000)].try_into().unwrap()
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		assert_noop!(
+			Dex::set_fee_tiers(
+				RuntimeOrigin::root(),
+				vec![Permill::from_percent(100)].try_into().unwrap()
+			),
+			Error::<Test>::InvalidFeeTier
+		);
+	})
+}
+
+#[test]
+fn swaps_charge_the_fee_of_the_pool() {
+	new_test_ext().execute_with(|| {
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 2_000u128);
+
+		//a 0.05% pool and a 1% pool with the same reserves
+		for (asset_id, fee) in [(1u32, Permill::from_parts(500)), (3u32, Permill::from_percent(1))]
+		{
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(
+				asset_id,
+				&account_id,
+				1_000u128
+			)
+			.is_ok());
+			assert_ok!(Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(fee),
+				500u128,
+				500u128,
+				None
+			));
+		}
+
+		let stable_amount =
+			Dex::get_input_convert(100u128, 500u128, 500u128, Permill::from_parts(500)).unwrap();
+		let volatile_amount =
+			Dex::get_input_convert(100u128, 500u128, 500u128, Permill::from_percent(1)).unwrap();
+		assert!(stable_amount > volatile_amount);
+
+		assert_ok!(Dex::currency_to_asset(sender.clone(), 100u128, 1u32, stable_amount, None));
+		assert_ok!(Dex::currency_to_asset(sender, 100u128, 3u32, volatile_amount, None));
+
+		assert_eq!(
+			Dex::pools((AssetKind::Native, AssetKind::Asset(1u32))).unwrap().reserve_b,
+			500u128 - stable_amount
+		);
+		assert_eq!(
+			Dex::pools((AssetKind::Native, AssetKind::Asset(3u32))).unwrap().reserve_b,
+			500u128 - volatile_amount
+		);
+	})
+}
+
+#[test]
+fn swaps_send_the_protocol_fee_to_the_recipient() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let recipient = ProtocolFeeRecipientAccount::get();
+		let sender = RuntimeOrigin::signed(account_id);
+		let fee = Permill::from_percent(1);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		//the recipient needs currency to hold fungible assets
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 3_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 10u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 3_000u128).is_ok()
+		);
+
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(fee),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		//switch the protocol fee on
+		let parameters =
+			Parameters { protocol_fee_share: Permill::from_percent(50), ..Dex::parameters() };
+		assert_ok!(Dex::set_parameters(RuntimeOrigin::root(), parameters));
+
+		//half of the 1% fee paid on 600 currency goes to the recipient
+		let asset_amount = Dex::get_input_convert(600u128, 1_000u128, 1_000u128, fee).unwrap();
+		assert_ok!(Dex::currency_to_asset(sender.clone(), 600u128, asset_id, asset_amount, None));
+
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&recipient), 13u128);
+		assert_eq!(Dex::protocol_fees_collected(&pool_id), (3u128, 0u128));
+		System::assert_has_event(
+			Event::ProtocolFeeCollected {
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				asset: AssetKind::Native,
+				amount: 3u128,
+			}
+			.into(),
+		);
+
+		//the protocol fee does not stay in the reserves
+		let pool = Dex::pools(&pool_id).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 1_000u128 + 600u128 - 3u128);
+		assert_eq!(pool.reserve_b, 1_000u128 - asset_amount);
+
+		//the same happens with the fungible asset side
+		let currency_amount =
+			Dex::get_input_convert(600u128, pool.reserve_b, pool.reserve_a, fee).unwrap();
+		assert_ok!(Dex::asset_to_currency(sender, 600u128, asset_id, currency_amount, None));
+
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &recipient), 3u128);
+		assert_eq!(Dex::protocol_fees_collected(&pool_id), (3u128, 3u128));
+	})
+}
+
+#[test]
+fn protocol_fees_the_recipient_cannot_receive_stay_in_the_pool() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let recipient = ProtocolFeeRecipientAccount::get();
+		let sender = RuntimeOrigin::signed(account_id);
+		let fee = Permill::from_percent(1);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		//the recipient does not exist, so it cannot hold a fungible asset that is not sufficient
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 3_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 3_000u128).is_ok()
+		);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(fee),
+			1_000u128,
+			1_000u128,
+			None
+		));
+		let parameters =
+			Parameters { protocol_fee_share: Permill::from_percent(50), ..Dex::parameters() };
+		assert_ok!(Dex::set_parameters(RuntimeOrigin::root(), parameters));
+
+		//the swap goes through and the whole fee is left to the providers
+		let currency_amount = Dex::get_input_convert(600u128, 1_000u128, 1_000u128, fee).unwrap();
+		assert_ok!(Dex::asset_to_currency(sender, 600u128, asset_id, currency_amount, None));
+
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &recipient), 0u128);
+		assert_eq!(Dex::protocol_fees_collected(&pool_id), (0u128, 0u128));
+		let pool = Dex::pools(&pool_id).unwrap();
+		assert_eq!(pool.reserve_a, 1_000u128 - currency_amount);
+		assert_eq!(pool.reserve_b, 1_000u128 + 600u128);
+	})
+}
+
+#[test]
+fn destroy_pool_refunds_the_metadata_deposit_to_the_creator() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 3u32;
+		let liquidity_asset_id = 1000u32;
+		let account_id = 1u64;
+		let recipient = DustRecipientAccount::get();
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//the pallet and the dust recipient accounts must exist to hold the asset
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 1_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 100u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 1_000u128).is_ok()
+		);
+
+		MetadataDepositBase::set(5);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+		assert_eq!(Dex::metadata_deposit(liquidity_asset_id), Some((account_id, 5u128)));
+		assert_eq!(Balances::free_balance(account_id), 1_000u128 - 100u128 - 5u128);
+
+		//the creator withdraws everything but the locked liquidity
+		assert_ok!(Dex::remove_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			100u128 - MinimumLiquidity::get(),
+			0u128,
+			0u128,
+			None
+		));
+		assert_ok!(Dex::destroy_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+
+		//the deposit released by the assets pallet goes back to the creator, not to the pallet
+		assert_eq!(Balances::free_balance(account_id), 1_000u128 - MinimumLiquidity::get());
+		assert_eq!(Balances::reserved_balance(Dex::account_id()), 0u128);
+		assert_eq!(Dex::metadata_deposit(liquidity_asset_id), None);
+	})
+}
+
+#[test]
+fn set_parameters_successfully() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		//the parameters keep the values used before they were stored on chain
+		assert_eq!(
+			Dex::parameters(),
+			Parameters {
+				default_fee: FEE,
+				protocol_fee_share: Permill::zero(),
+				max_hops: 4,
+				min_liquidity: MinimumLiquidity::get(),
+				max_price_impact: Permill::from_percent(100),
+			}
+		);
+
+		let parameters = Parameters {
+			default_fee: Permill::from_percent(1),
+			protocol_fee_share: Permill::from_percent(10),
+			max_hops: 3,
+			min_liquidity: 20u128,
+			max_price_impact: Permill::from_percent(20),
+		};
+
+		//only the admin origin can update the parameters
+		assert_noop!(
+			Dex::set_parameters(RuntimeOrigin::signed(1u64), parameters.clone()),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		//max_hops cannot be greater than MaxHops nor smaller than two
+		assert_noop!(
+			Dex::set_parameters(
+				RuntimeOrigin::root(),
+				Parameters { max_hops: 5, ..parameters.clone() }
+			),
+			Error::<Test>::InvalidParameters
+		);
+		assert_noop!(
+			Dex::set_parameters(
+				RuntimeOrigin::root(),
+				Parameters { max_hops: 1, ..parameters.clone() }
+			),
+			Error::<Test>::InvalidParameters
+		);
+
+		//the default fee must be lower than 100%
+		assert_noop!(
+			Dex::set_parameters(
+				RuntimeOrigin::root(),
+				Parameters { default_fee: Permill::from_percent(100), ..parameters.clone() }
+			),
+			Error::<Test>::InvalidParameters
+		);
+
+		assert_ok!(Dex::set_parameters(RuntimeOrigin::root(), parameters.clone()));
+		assert_eq!(Dex::parameters(), parameters);
+		System::assert_last_event(Event::ParametersUpdated { parameters }.into());
+	})
+}
+
+#[test]
+fn create_pool_uses_the_default_fee_and_minimum_liquidity() {
+	new_test_ext().execute_with(|| {
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		for asset_id in [1u32, 3u32] {
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128)
+				.is_ok());
+		}
+
+		assert_ok!(Dex::set_parameters(
+			RuntimeOrigin::root(),
+			Parameters {
+				default_fee: Permill::from_percent(2),
+				min_liquidity: 50u128,
+				..Dex::parameters()
+			}
+		));
+
+		//the deposit does not mint more than the minimum liquidity
+		assert_noop!(
+			Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(1u32),
+				None,
+				50u128,
+				50u128,
+				None
+			),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+
+		//the default fee does not need to be one of the tiers
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(3u32),
+			None,
+			60u128,
+			60u128,
+			None
+		));
+		assert_eq!(
+			Dex::pools((AssetKind::Native, AssetKind::Asset(3u32))).unwrap_or_default().fee,
+			Permill::from_percent(2)
+		);
+
+		//the minimum liquidity is locked in the pallet account
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(4u32, &account_id), 10u128);
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(4u32, &Dex::account_id()), 50u128);
+	})
+}
+
+#[test]
+fn swaps_fail_above_the_parameter_limits() {
+	new_test_ext().execute_with(|| {
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 1_000u128);
+		for asset_id in [1u32, 3u32] {
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(
+				asset_id,
+				&account_id,
+				1_000u128
+			)
+			.is_ok());
+			assert_ok!(Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				100u128,
+				100u128,
+				None
+			));
+		}
+
+		assert_ok!(Dex::set_parameters(
+			RuntimeOrigin::root(),
+			Parameters {
+				max_hops: 2,
+				max_price_impact: Permill::from_percent(20),
+				..Dex::parameters()
+			}
+		));
+
+		//the path has more assets than max_hops
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				sender.clone(),
+				vec![AssetKind::Asset(1u32), AssetKind::Native, AssetKind::Asset(3u32)]
+					.try_into()
+					.unwrap(),
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+
+		//30 / (100 + 30) is above the 20% price impact limit
+		assert_noop!(
+			Dex::currency_to_asset(sender.clone(), 30u128, 1u32, 0u128, None),
+			Error::<Test>::PriceImpactTooHigh
+		);
+
+		//20 / (100 + 20) is below it
+		assert_ok!(Dex::currency_to_asset(sender, 20u128, 1u32, 0u128, None));
+	})
+}
+
+#[test]
+fn minimum_liquidity_stays_in_the_pool() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 1u32;
+		let liquidity_asset_id = 1000u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		assert_ok!(Dex::set_parameters(
+			RuntimeOrigin::root(),
+			Parameters { min_liquidity: 10u128, ..Dex::parameters() }
+		));
+
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//the creator withdraws every liquidity asset received
+		assert_ok!(Dex::remove_liquidity(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			90u128,
+			0u128,
+			0u128,
+			None
+		));
+
+		//the share of the locked liquidity stays in the pool
+		let pool = Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).unwrap_or_default();
+		assert_eq!(pool.reserve_a, 10u128);
+		assert_eq!(pool.reserve_b, 10u128);
+		assert_eq!(<Test as crate::Config>::Fungibles::total_issuance(liquidity_asset_id), 10u128);
+	})
+}
+
+#[test]
+fn destroy_pool_without_liquidity_providers() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let liquidity_asset_id = 1000u32;
+		let account_id = 1u64;
+		let recipient = DustRecipientAccount::get();
+		let sender = RuntimeOrigin::signed(account_id);
+
+		//the pallet and the dust recipient accounts must exist to hold the asset
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 100u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		assert_ok!(Dex::set_parameters(
+			RuntimeOrigin::root(),
+			Parameters { min_liquidity: 10u128, ..Dex::parameters() }
+		));
+
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//the creator still provides liquidity
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::root(), AssetKind::Native, AssetKind::Asset(asset_id)),
+			Error::<Test>::LiquidityProvidersLeft
+		);
+
+		assert_ok!(Dex::remove_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			90u128,
+			0u128,
+			0u128,
+			None
+		));
+
+		//only the locked liquidity is left, but only the admin can destroy the pool
+		assert_noop!(
+			Dex::destroy_pool(sender, AssetKind::Native, AssetKind::Asset(asset_id)),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Dex::destroy_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native
+		));
+
+		//the share of the locked liquidity is swept to the dust recipient
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&recipient), 110u128);
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &recipient), 10u128);
+		assert!(Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).is_none());
+		assert!(!<Test as crate::Config>::Fungibles::asset_exists(liquidity_asset_id));
+
+		System::assert_last_event(
+			Event::PoolDestroyed {
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				liquidity_asset_id,
+				dust_a: 10u128,
+				dust_b: 10u128,
+			}
+			.into(),
+		);
+	})
+}
+
+#[test]
+fn admin_destroys_a_pool_after_the_grace_period() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let liquidity_asset_id = 1000u32;
+		let account_id = 1u64;
+		let recipient = DustRecipientAccount::get();
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 100u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//only the admin can schedule the destruction of a pool
+		assert_noop!(
+			Dex::schedule_pool_destruction(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id)
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Dex::schedule_pool_destruction(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+		assert_eq!(Dex::scheduled_destruction(&pool_id), Some(11u64));
+		assert_noop!(
+			Dex::schedule_pool_destruction(
+				RuntimeOrigin::root(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id)
+			),
+			Error::<Test>::DestructionAlreadyScheduled
+		);
+
+		//during the grace period providers can only withdraw
+		assert_noop!(
+			Dex::add_liquidity(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::PoolBeingDestroyed
+		);
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::root(), AssetKind::Native, AssetKind::Asset(asset_id)),
+			Error::<Test>::GracePeriodNotOver
+		);
+		assert_ok!(Dex::remove_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			50u128,
+			0u128,
+			0u128,
+			None
+		));
+
+		//a limit order and a dca schedule still use the pool
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&2u64, 100u128);
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &2u64, 100u128).is_ok());
+		assert_ok!(Dex::place_limit_order(
+			RuntimeOrigin::signed(2u64),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native,
+			40u128,
+			100u128,
+			50u64
+		));
+		assert_ok!(Dex::schedule_dca(
+			RuntimeOrigin::signed(2u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			10u128,
+			30u128,
+			20u64,
+			Permill::from_percent(10)
+		));
+		assert_eq!(Balances::reserved_balance(2u64), 5u128);
+
+		//once the grace period is over the admin can destroy a pool with providers left
+		System::set_block_number(11);
+		assert_noop!(
+			Dex::destroy_pool(sender.clone(), AssetKind::Native, AssetKind::Asset(asset_id)),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Dex::destroy_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+		System::assert_last_event(RuntimeEvent::Dex(Event::PoolRetired {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			liquidity_asset_id,
+			reserve_a: 50u128,
+			reserve_b: 50u128,
+		}));
+		assert_eq!(Dex::scheduled_destruction(&pool_id), None);
+		assert!(Dex::pools(&pool_id).is_none());
+
+		//the limit order is given back at once, the dca schedule ends at its next period
+		System::assert_has_event(RuntimeEvent::Dex(Event::LimitOrderCancelled {
+			order_id: 0,
+			owner: 2u64,
+		}));
+		assert_eq!(Assets::balance(asset_id, 2u64), 100u128);
+		Dex::on_initialize(2);
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaTerminated {
+			dca_id: 0,
+			owner: 2u64,
+			reason: DcaEnd::PoolDestroyed,
+		}));
+		assert_eq!(Balances::reserved_balance(2u64), 0u128);
+
+		//the provider left withdraws its share instead of losing it to the dust recipient
+		assert_noop!(
+			Dex::withdraw_retired_liquidity(RuntimeOrigin::signed(2u64), liquidity_asset_id),
+			Error::<Test>::LiqAmountZero
+		);
+		assert_noop!(
+			Dex::withdraw_retired_liquidity(sender.clone(), asset_id),
+			Error::<Test>::RetiredPoolNotFound
+		);
+		assert_ok!(Dex::withdraw_retired_liquidity(sender, liquidity_asset_id));
+		System::assert_has_event(RuntimeEvent::Dex(Event::RetiredLiquidityWithdrawn {
+			who: account_id,
+			liquidity_asset_id,
+			liquidity_amount: 40u128,
+			amount_a: 40u128,
+			amount_b: 40u128,
+		}));
+		assert_eq!(Balances::free_balance(account_id), 290u128);
+		assert_eq!(Assets::balance(asset_id, account_id), 290u128);
+
+		//the last provider finishes the destruction, the share of the locked liquidity is swept
+		System::assert_last_event(RuntimeEvent::Dex(Event::PoolDestroyed {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			liquidity_asset_id,
+			dust_a: 10u128,
+			dust_b: 10u128,
+		}));
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&recipient), 110u128);
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &recipient), 10u128);
+		assert_eq!(Dex::retired_pool(liquidity_asset_id), None);
+		assert!(!<Test as crate::Config>::Fungibles::asset_exists(liquidity_asset_id));
+	})
+}
+
+#[test]
+fn create_pool_reserves_a_deposit_refunded_on_destruction() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+		PoolCreationDeposit::set(20u128);
+
+		//without locked liquidity the creator can withdraw the whole pool
+		MinimumLiquidity::set(0);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 100u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//the deposit is reserved from the creator
+		assert_eq!(<Test as crate::Config>::Currency::reserved_balance(&account_id), 20u128);
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&account_id), 180u128);
+		assert_eq!(Dex::pool_deposit(&pool_id), Some((account_id, 20u128)));
+
+		assert_ok!(Dex::remove_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			100u128,
+			0u128,
+			0u128,
+			None
+		));
+		assert_ok!(Dex::destroy_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+
+		//the deposit is refunded when the pool is destroyed
+		assert_eq!(<Test as crate::Config>::Currency::reserved_balance(&account_id), 0u128);
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&account_id), 300u128);
+		assert_eq!(Dex::pool_deposit(&pool_id), None);
+	})
+}
+
+#[test]
+fn destroy_pool_sweeps_the_reserves_once_the_liquidity_asset_is_destroyed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let liquidity_asset_id = 1000u32;
+		let account_id = 1u64;
+		let recipient = DustRecipientAccount::get();
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 100u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 100u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//the liquidity asset gets more approvals than DestroyItemsLimit
+		for delegate in 10u64..21u64 {
+			assert_ok!(Assets::approve_transfer(
+				sender.clone(),
+				liquidity_asset_id.into(),
+				delegate,
+				1u128
+			));
+		}
+		assert_ok!(Dex::remove_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			100u128 - MinimumLiquidity::get(),
+			0u128,
+			0u128,
+			None
+		));
+		assert_noop!(
+			Dex::continue_pool_destruction(sender.clone(), liquidity_asset_id),
+			Error::<Test>::DestructionNotPending
+		);
+
+		//the first call destroys only part of the approvals and keeps the reserves
+		assert_ok!(Dex::destroy_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+		System::assert_last_event(RuntimeEvent::Dex(Event::PoolDestructionPending {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			liquidity_asset_id,
+		}));
+		assert!(Dex::pools((AssetKind::Native, AssetKind::Asset(asset_id))).is_none());
+		assert!(Dex::destroying_pool(liquidity_asset_id).is_some());
+		assert!(<Test as crate::Config>::Fungibles::asset_exists(liquidity_asset_id));
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&recipient), 100u128);
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &recipient), 0u128);
+
+		//anyone can destroy the rest, then the reserves are swept
+		assert_ok!(Dex::continue_pool_destruction(RuntimeOrigin::signed(2u64), liquidity_asset_id));
+		System::assert_last_event(RuntimeEvent::Dex(Event::PoolDestroyed {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			liquidity_asset_id,
+			dust_a: 10u128,
+			dust_b: 10u128,
+		}));
+		assert_eq!(Dex::destroying_pool(liquidity_asset_id), None);
+		assert!(!<Test as crate::Config>::Fungibles::asset_exists(liquidity_asset_id));
+		assert_eq!(<Test as crate::Config>::Currency::free_balance(&recipient), 110u128);
+		assert_eq!(<Test as crate::Config>::Fungibles::balance(asset_id, &recipient), 10u128);
+		assert_noop!(
+			Dex::continue_pool_destruction(sender, liquidity_asset_id),
+			Error::<Test>::DestructionNotPending
+		);
+	})
+}
+
+#[test]
+fn create_pool_only_lists_allowed_assets() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		RestrictListings::set(true);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		//the asset is not in the allow-list
+		assert_noop!(
+			Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				100u128,
+				100u128,
+				None
+			),
+			Error::<Test>::AssetNotAllowed
+		);
+
+		//only the admin can allow an asset
+		assert_noop!(
+			Dex::set_asset_allowed(sender.clone(), asset_id, true),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Dex::set_asset_allowed(RuntimeOrigin::root(), asset_id, true));
+		assert!(Dex::asset_allowed(asset_id));
+		System::assert_last_event(Event::AssetListingUpdated { asset_id, allowed: true }.into());
+
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+	})
+}
+
+#[test]
+fn paused_pools_only_allow_removing_liquidity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//only the pause origin can pause a pool
+		assert_noop!(
+			Dex::pause_pool(sender.clone(), AssetKind::Native, AssetKind::Asset(asset_id)),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Dex::pause_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native
+		));
+		System::assert_last_event(
+			Event::PoolStatusUpdated {
+				asset_a: AssetKind::Native,
+				asset_b: AssetKind::Asset(asset_id),
+				status: PoolStatus::Paused,
+			}
+			.into(),
+		);
+
+		assert_noop!(
+			Dex::currency_to_asset(sender.clone(), 10u128, asset_id, 0u128, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				sender.clone(),
+				vec![AssetKind::Asset(asset_id), AssetKind::Native].try_into().unwrap(),
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			Dex::add_liquidity(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				10u128,
+				10u128,
+				0u128,
+				None
+			),
+			Error::<Test>::PoolPaused
+		);
+
+		//providers can still exit
+		assert_ok!(Dex::remove_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			10u128,
+			0u128,
+			0u128,
+			None
+		));
+
+		assert_ok!(Dex::unpause_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+		assert_ok!(Dex::currency_to_asset(sender, 10u128, asset_id, 0u128, None));
+	})
+}
+
+#[test]
+fn pause_all_stops_every_pool() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 1_000u128);
+		for asset_id in [1u32, 3u32] {
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(
+				asset_id,
+				&account_id,
+				1_000u128
+			)
+			.is_ok());
+			assert_ok!(Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				100u128,
+				100u128,
+				None
+			));
+		}
+
+		assert_noop!(Dex::pause_all(sender.clone()), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(Dex::pause_all(RuntimeOrigin::root()));
+		assert!(Dex::paused());
+		System::assert_last_event(Event::GlobalPauseUpdated { paused: true }.into());
+
+		assert_noop!(
+			Dex::asset_to_asset(sender.clone(), 1u32, 3u32, 10u128, 0u128, None),
+			Error::<Test>::DexPaused
+		);
+		assert_noop!(
+			Dex::create_pool(
+				sender.clone(),
+				AssetKind::Asset(1u32),
+				AssetKind::Asset(3u32),
+				Some(FEE),
+				20u128,
+				20u128,
+				None
+			),
+			Error::<Test>::DexPaused
+		);
+		assert_ok!(Dex::remove_liquidity(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(3u32),
+			10u128,
+			0u128,
+			0u128,
+			None
+		));
+
+		//a pool paused on its own stays paused after the global pause is lifted
+		assert_ok!(Dex::pause_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(3u32)
+		));
+		assert_ok!(Dex::unpause_all(RuntimeOrigin::root()));
+		assert!(!Dex::paused());
+		assert_ok!(Dex::currency_to_asset(sender.clone(), 10u128, 1u32, 0u128, None));
+		assert_noop!(
+			Dex::currency_to_asset(sender, 10u128, 3u32, 0u128, None),
+			Error::<Test>::PoolPaused
+		);
+	})
+}
+
+#[test]
+fn twap_averages_the_price_over_the_window() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		//the first observation is taken when the pool is created, at 10 seconds
+		Timestamp::set_timestamp(10_000);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+		assert_ok!(Dex::increase_observation_cardinality(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			4
+		));
+
+		//the price of 1 lasts 10 seconds until the swap
+		Timestamp::set_timestamp(20_000);
+		assert_ok!(Dex::currency_to_asset(sender.clone(), 10u128, asset_id, 0u128, None));
+
+		//a second swap in the same block does not update the accumulators
+		assert_ok!(Dex::currency_to_asset(sender, 10u128, asset_id, 0u128, None));
+		assert_eq!(Dex::price_observations(&pool_id).len(), 2);
+
+		let pool = Dex::pools(&pool_id).unwrap();
+		assert_eq!(pool.price_b_cumulative, FixedU128::from_u32(10));
+		let price = FixedU128::saturating_from_rational(pool.reserve_a, pool.reserve_b);
+
+		//the new price lasts another 10 seconds
+		Timestamp::set_timestamp(30_000);
+		assert_eq!(Dex::twap(asset_id, 10), Ok(price));
+		assert_eq!(
+			Dex::twap(asset_id, 20),
+			Ok(FixedU128::from_inner(
+				(FixedU128::from_u32(10).into_inner() + price.into_inner() * 10) / 20
+			))
+		);
+
+		//there is no observation older than the pool
+		assert_eq!(Dex::twap(asset_id, 25), Err(Error::<Test>::InsufficientPriceHistory));
+	})
+}
+
+#[test]
+fn observations_are_a_ring_buffer_and_observe_interpolates() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 300u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 300u128).is_ok()
+		);
+
+		System::set_block_number(1);
+		Timestamp::set_timestamp(10_000);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			100u128,
+			100u128,
+			None
+		));
+
+		//only the admin can grow the history, up to MaxObservations
+		assert_noop!(
+			Dex::increase_observation_cardinality(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				2
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Dex::increase_observation_cardinality(
+				RuntimeOrigin::root(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				5
+			),
+			Error::<Test>::InvalidCardinality
+		);
+		assert_ok!(Dex::increase_observation_cardinality(
+			RuntimeOrigin::root(),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native,
+			2
+		));
+		System::assert_last_event(RuntimeEvent::Dex(Event::ObservationCardinalityIncreased {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			cardinality_next: 2,
+		}));
+
+		//the cardinality can only grow
+		assert_noop!(
+			Dex::increase_observation_cardinality(
+				RuntimeOrigin::root(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				2
+			),
+			Error::<Test>::InvalidCardinality
+		);
+
+		System::set_block_number(2);
+		Timestamp::set_timestamp(20_000);
+		assert_ok!(Dex::currency_to_asset(sender.clone(), 10u128, asset_id, 0u128, None));
+		let price = Dex::spot_price(asset_id).unwrap().0;
+
+		//the third observation overwrites the one taken when the pool was created
+		System::set_block_number(3);
+		Timestamp::set_timestamp(30_000);
+		assert_ok!(Dex::currency_to_asset(sender, 10u128, asset_id, 0u128, None));
+
+		let observations = Dex::price_observations(&pool_id);
+		assert_eq!(observations.len(), 2);
+		assert_eq!(Dex::observation_state(&pool_id).index, 0);
+		assert_eq!(observations[0].block_number, 3);
+		assert_eq!(observations[0].timestamp, 30);
+		assert_eq!(observations[1].block_number, 2);
+		assert_eq!(observations[1].timestamp, 20);
+
+		//halfway between the two observations, and at both of them
+		let at_20 = FixedU128::from_u32(10);
+		let at_25 = FixedU128::from_inner(at_20.into_inner() + price.into_inner() * 5);
+		let at_30 = FixedU128::from_inner(at_20.into_inner() + price.into_inner() * 10);
+		let cumulatives = Dex::observe(asset_id, vec![10, 5, 0]).unwrap();
+		assert_eq!(
+			cumulatives.into_iter().map(|(_, price_b)| price_b).collect::<Vec<_>>(),
+			vec![at_20, at_25, at_30]
+		);
+
+		//the observation of the pool creation is gone
+		assert_eq!(Dex::observe(asset_id, vec![15]), Err(Error::<Test>::InsufficientPriceHistory));
+	})
+}
+
+#[test]
+fn spot_price_and_quote() {
+	new_test_ext().execute_with(|| {
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 3_000u128);
+		for asset_id in [1u32, 3u32] {
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(
+				asset_id,
+				&account_id,
+				3_000u128
+			)
+			.is_ok());
+		}
+
+		//a pool of 1500 currency and 1000 assets
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(1u32),
+			Some(FEE),
+			1_500u128,
+			1_000u128,
+			None
+		));
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(3u32),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		//the ratio of the reserves keeps its precision
+		assert_eq!(
+			Dex::spot_price(1u32),
+			Ok((
+				FixedU128::saturating_from_rational(3u128, 2u128),
+				FixedU128::saturating_from_rational(2u128, 3u128)
+			))
+		);
+		assert_eq!(Dex::spot_price(2u32), Err(Error::<Test>::PoolNotFound));
+
+		//the quote includes the fee of every pool in the path
+		let path = [AssetKind::Asset(1u32), AssetKind::Native, AssetKind::Asset(3u32)];
+		let currency_amount = Dex::get_input_convert(100u128, 1_000u128, 1_500u128, FEE).unwrap();
+		let asset_amount =
+			Dex::get_input_convert(currency_amount, 1_000u128, 1_000u128, FEE).unwrap();
+		assert_eq!(Dex::quote(100u128, &path), Ok(asset_amount));
+		assert_eq!(Dex::quote(100u128, &path[..1]), Err(Error::<Test>::InvalidPath.into()));
+	})
+}
+
+#[test]
+fn runtime_api_helpers() {
+	new_test_ext().execute_with(|| {
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 3_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 3_000u128).is_ok()
+		);
+		assert_ok!(Dex::create_pool(
+			sender,
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_500u128,
+			1_000u128,
+			None
+		));
+
+		let pool = Dex::pools(&pool_id).unwrap();
+		let liquidity_supply = Assets::total_issuance(pool.liquidity_asset_id);
+		let pool_info = Dex::pool_info(pool.clone());
+		assert_eq!(pool_info.reserve_a, 1_500u128);
+		assert_eq!(pool_info.reserve_b, 1_000u128);
+		assert_eq!(pool_info.liquidity_supply, liquidity_supply);
+		assert!(!pool_info.paused);
+		assert_eq!(Dex::list_pools(), vec![pool_info]);
+
+		//the whole supply is worth the whole reserves
+		assert_eq!(Dex::liquidity_value(&pool, liquidity_supply), Ok((1_500u128, 1_000u128)));
+
+		//the input needed for an exact output includes the fee
+		let path = [AssetKind::Native, AssetKind::Asset(asset_id)];
+		let currency_amount = Dex::get_output_convert(100u128, 1_500u128, 1_000u128, FEE).unwrap();
+		assert_eq!(Dex::quote_exact_out(100u128, &path), Ok(currency_amount));
+		assert!(Dex::quote(currency_amount, &path).unwrap() >= 100u128);
+
+		//a global pause is reported by every pool
+		assert_ok!(Dex::pause_all(RuntimeOrigin::root()));
+		assert!(Dex::list_pools()[0].paused);
+	})
+}
+
+#[test]
+fn flash_swap_requires_the_constant_product_to_hold() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 3_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 3_000u128).is_ok()
+		);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		let flash_swap = |amount_a_out: u128, amount_b_out: u128| {
+			Dex::flash_swap(
+				sender.clone(),
+				AssetKind::Asset(asset_id),
+				AssetKind::Native,
+				amount_a_out,
+				amount_b_out,
+				vec![],
+				None,
+			)
+		};
+		assert_noop!(flash_swap(0, 0), Error::<Test>::AssetAmountZero);
+		assert_noop!(flash_swap(1_000, 0), Error::<Test>::InsufficientPoolReserve);
+
+		//borrowing 10 assets costs as much currency as swapping for them
+		let currency_amount = Dex::get_output_convert(10u128, 1_000u128, 1_000u128, FEE).unwrap();
+		FlashSwapRepayment::set((currency_amount - 1, 0));
+		assert_noop!(flash_swap(10, 0), Error::<Test>::InvariantViolated);
+
+		//the pool cannot be used while its reserves are lent
+		FlashSwapRepayment::set((currency_amount, 0));
+		FlashSwapReenters::set(Some(asset_id));
+		assert_noop!(flash_swap(10, 0), Error::<Test>::PoolLocked);
+		FlashSwapReenters::set(None);
+
+		assert_ok!(flash_swap(10, 0));
+		System::assert_last_event(RuntimeEvent::Dex(Event::FlashSwapExecuted {
+			borrower: account_id,
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			amount_a_out: 0,
+			amount_b_out: 10,
+			amount_a_in: currency_amount,
+			amount_b_in: 0,
+		}));
+		let pool = Dex::pools(&pool_id).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (1_000 + currency_amount, 990));
+		assert!(!Dex::flash_swap_locked(&pool_id));
+
+		//the borrowed assets can also be repaid with a fee on top
+		FlashSwapRepayment::set((0, 10));
+		assert_noop!(flash_swap(10, 0), Error::<Test>::InvariantViolated);
+		FlashSwapRepayment::set((0, 11));
+		assert_ok!(flash_swap(10, 0));
+		assert_eq!(Dex::pools(&pool_id).unwrap().reserve_b, 991);
+	})
+}
+
+#[test]
+fn nested_flash_swaps_keep_every_lent_pool_locked() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+		let pool_id = |asset_id: u32| (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 5_000u128);
+		for asset_id in [1u32, 2u32] {
+			assert_ok!(Dex::create_asset_helper(asset_id));
+			assert!(<Test as crate::Config>::Fungibles::mint_into(
+				asset_id,
+				&account_id,
+				3_000u128
+			)
+			.is_ok());
+			assert_ok!(Dex::create_pool(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				Some(FEE),
+				1_000u128,
+				1_000u128,
+				None
+			));
+		}
+
+		//the same repayment covers borrowing 10 assets of the outer pool and 1 of the inner one
+		let currency_amount = Dex::get_output_convert(10u128, 1_000u128, 1_000u128, FEE).unwrap();
+		FlashSwapRepayment::set((currency_amount, 0));
+		let flash_swap = || {
+			Dex::flash_swap(
+				sender.clone(),
+				AssetKind::Native,
+				AssetKind::Asset(1),
+				0,
+				10,
+				vec![],
+				None,
+			)
+		};
+
+		//the outer pool stays locked while the handler holds the loan of the inner one
+		FlashSwapNests::set(Some(2));
+		FlashSwapReenters::set(Some(1));
+		assert_noop!(flash_swap(), Error::<Test>::PoolLocked);
+		FlashSwapReenters::set(None);
+
+		FlashSwapNests::set(Some(2));
+		assert_ok!(flash_swap());
+		let pool = Dex::pools(&pool_id(1)).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (1_000 + currency_amount, 990));
+		let pool = Dex::pools(&pool_id(2)).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (1_000 + currency_amount, 999));
+		assert!(!Dex::flash_swap_locked(&pool_id(1)));
+		assert!(!Dex::flash_swap_locked(&pool_id(2)));
+
+		//the reserves stored for the pools match what the pallet holds
+		let pallet_account = Dex::account_id();
+		assert_eq!(Balances::free_balance(pallet_account), 2_000 + 2 * currency_amount);
+		assert_eq!(Assets::balance(1, pallet_account), 990);
+		assert_eq!(Assets::balance(2, pallet_account), 999);
+	})
+}
+
+#[test]
+fn keepers_fill_limit_orders_once_the_price_crosses_their_limit() {
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	let mut ext = new_test_ext();
+	ext.register_extension(TransactionPoolExt::new(pool));
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let owner = 2u64;
+		let keeper = 3u64;
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&1u64, 3_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&owner, 10u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&keeper, 10u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		for account_id in [1u64, owner] {
+			assert!(<Test as crate::Config>::Fungibles::mint_into(
+				asset_id,
+				&account_id,
+				3_000u128
+			)
+			.is_ok());
+		}
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(1u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		//sell 100 assets for at least 110 currency, the assets go to the pallet account
+		let place_order = |min_amount_out: u128| {
+			Dex::place_limit_order(
+				RuntimeOrigin::signed(owner),
+				AssetKind::Asset(asset_id),
+				AssetKind::Native,
+				100u128,
+				min_amount_out,
+				10,
+			)
+		};
+		assert_noop!(place_order(0), Error::<Test>::CurrencyAmountZero);
+		assert_ok!(place_order(120));
+		assert_ok!(place_order(110));
+		assert_eq!(Assets::balance(asset_id, owner), 2_800u128);
+
+		//the book is sorted by limit price
+		let book = Dex::order_book((AssetKind::Asset(asset_id), AssetKind::Native));
+		assert_eq!(book.iter().map(|(_, order_id)| *order_id).collect::<Vec<_>>(), vec![1, 0]);
+
+		//nothing is submitted while the pool price is below the limits
+		assert_ok!(Dex::set_keeper(RuntimeOrigin::root(), keeper, true));
+		UintAuthorityId::set_all_keys(vec![keeper]);
+		Dex::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		//buying assets raises their price
+		assert_ok!(Dex::currency_to_asset(RuntimeOrigin::signed(1u64), 500u128, asset_id, 1, None));
+		let pool = Dex::pools(&(AssetKind::Native, AssetKind::Asset(asset_id))).unwrap();
+		let amount_out =
+			Dex::get_input_convert(99u128, pool.reserve_b, pool.reserve_a, FEE).unwrap();
+		assert_eq!(Dex::fillable_orders(), vec![1, 0]);
+
+		//the keeper submits both orders in an unsigned transaction
+		Dex::offchain_worker(1);
+		let transaction = pool_state.write().transactions.pop().unwrap();
+		let transaction = Extrinsic::decode(&mut &*transaction).unwrap();
+		assert_eq!(transaction.signature, None);
+		let call = match transaction.call {
+			RuntimeCall::Dex(call) => call,
+			_ => panic!("unexpected call"),
+		};
+		let (payload, signature) = match call.clone() {
+			crate::Call::execute_limit_orders { payload, signature } => (payload, signature),
+			_ => panic!("unexpected call"),
+		};
+		assert_eq!(payload.order_ids, vec![1, 0]);
+		assert_eq!(payload.public, UintAuthorityId(keeper));
+		assert!(<Dex as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call)
+			.is_ok());
+
+		//the payload must be signed by the keeper it names
+		let forged = crate::Call::execute_limit_orders {
+			payload: payload.clone(),
+			signature: TestSignature(4u64, payload.encode()),
+		};
+		assert_eq!(
+			<Dex as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &forged),
+			InvalidTransaction::BadProof.into()
+		);
+
+		assert_ok!(Dex::execute_limit_orders(RuntimeOrigin::none(), payload, signature));
+		assert_eq!(Dex::limit_orders(1), None);
+		assert_eq!(Dex::limit_orders(0), None);
+		assert_eq!(Dex::order_book((AssetKind::Asset(asset_id), AssetKind::Native)).len(), 0);
+		assert_eq!(Dex::order_expiries(10).len(), 0);
+		System::assert_has_event(RuntimeEvent::Dex(Event::LimitOrderExecuted {
+			order_id: 1,
+			owner,
+			keeper,
+			amount_in: 100u128,
+			amount_out,
+			keeper_fee: 1u128,
+		}));
+		//the keeper fees are paid out of the escrow
+		assert_eq!(Assets::balance(asset_id, owner), 2_800u128);
+		assert_eq!(Assets::balance(asset_id, keeper), 2u128);
+		assert!(Balances::free_balance(owner) >= 10u128 + 2 * 110u128);
+
+		//filled orders cannot be submitted again
+		assert_eq!(
+			<Dex as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+	})
+}
+
+#[test]
+fn only_keepers_with_recent_payloads_fill_limit_orders() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+		let keeper = 3u64;
+		let payload = |block_number: u64, order_ids: Vec<u64>, public: u64| {
+			let payload =
+				KeeperPayload { block_number, order_ids, public: UintAuthorityId(public) };
+			let signature = TestSignature(public, payload.encode());
+			crate::Call::execute_limit_orders { payload, signature }
+		};
+		let validate = |call: &crate::Call<Test>| {
+			<Dex as ValidateUnsigned>::validate_unsigned(TransactionSource::External, call)
+		};
+
+		//only the admin chooses the keepers
+		assert_noop!(
+			Dex::set_keeper(RuntimeOrigin::signed(keeper), keeper, true),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_eq!(validate(&payload(10, vec![0], keeper)), InvalidTransaction::BadSigner.into());
+		assert_noop!(
+			Dex::execute_limit_orders(
+				RuntimeOrigin::none(),
+				KeeperPayload {
+					block_number: 10,
+					order_ids: vec![0],
+					public: UintAuthorityId(keeper)
+				},
+				TestSignature(keeper, vec![])
+			),
+			Error::<Test>::NotKeeper
+		);
+
+		assert_ok!(Dex::set_keeper(RuntimeOrigin::root(), keeper, true));
+		System::assert_last_event(RuntimeEvent::Dex(Event::KeeperUpdated {
+			who: keeper,
+			allowed: true,
+		}));
+		assert!(Dex::is_keeper(keeper));
+
+		//payloads from the future, older than KEEPER_LONGEVITY blocks or without orders are
+		//rejected before the orders are even read
+		assert_eq!(validate(&payload(11, vec![0], keeper)), InvalidTransaction::Future.into());
+		assert_eq!(validate(&payload(4, vec![0], keeper)), InvalidTransaction::Stale.into());
+		assert_eq!(
+			validate(&payload(10, vec![], keeper)),
+			InvalidTransaction::ExhaustsResources.into()
+		);
+		assert_eq!(
+			validate(&payload(10, vec![0; 5], keeper)),
+			InvalidTransaction::ExhaustsResources.into()
+		);
+
+		//a recent payload is still rejected while its orders cannot be filled
+		assert_eq!(validate(&payload(5, vec![0], keeper)), InvalidTransaction::Stale.into());
+
+		//a removed keeper cannot submit anymore
+		assert_ok!(Dex::set_keeper(RuntimeOrigin::root(), keeper, false));
+		assert!(!Dex::is_keeper(keeper));
+		assert_eq!(validate(&payload(10, vec![0], keeper)), InvalidTransaction::BadSigner.into());
+	})
+}
+
+#[test]
+fn limit_orders_can_be_cancelled_and_expire() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let owner = 1u64;
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&owner, 3_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &owner, 3_000u128).is_ok());
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(owner),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		//sell 100 currency for at least 200 assets, the currency is reserved
+		let place_order = |expires_at: u64| {
+			Dex::place_limit_order(
+				RuntimeOrigin::signed(owner),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				100u128,
+				200u128,
+				expires_at,
+			)
+		};
+		assert_noop!(place_order(1), Error::<Test>::InvalidExpiry);
+		assert_ok!(place_order(5));
+		assert_eq!(Balances::reserved_balance(owner), 100u128);
+
+		assert_noop!(
+			Dex::cancel_limit_order(RuntimeOrigin::signed(2u64), 0),
+			Error::<Test>::NotOrderOwner
+		);
+		assert_ok!(Dex::cancel_limit_order(RuntimeOrigin::signed(owner), 0));
+		assert_eq!(Balances::reserved_balance(owner), 0u128);
+		assert_eq!(Dex::order_expiries(5).len(), 0);
+		System::assert_last_event(RuntimeEvent::Dex(Event::LimitOrderCancelled {
+			order_id: 0,
+			owner,
+		}));
+		assert_noop!(
+			Dex::cancel_limit_order(RuntimeOrigin::signed(owner), 0),
+			Error::<Test>::OrderNotFound
+		);
+
+		//a block holds a limited number of expiring orders
+		for _ in 0..4 {
+			assert_ok!(place_order(5));
+		}
+		assert_noop!(place_order(5), Error::<Test>::TooManyOrders);
+		assert_eq!(Balances::reserved_balance(owner), 400u128);
+
+		Dex::on_initialize(5);
+		assert_eq!(Balances::reserved_balance(owner), 0u128);
+		assert_eq!(Dex::limit_orders(1), None);
+		assert_eq!(Dex::order_book((AssetKind::Native, AssetKind::Asset(asset_id))).len(), 0);
+		System::assert_last_event(RuntimeEvent::Dex(Event::LimitOrderExpired {
+			order_id: 4,
+			owner,
+		}));
+	})
+}
+
+#[test]
+fn expired_limit_order_stays_open_when_its_input_cannot_be_given_back() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let owner = 1u64;
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&owner, 3_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &owner, 3_000u128).is_ok());
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(owner),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		//sell 100 assets for at least 200 currency, the assets are held by the pallet
+		assert_ok!(Dex::place_limit_order(
+			RuntimeOrigin::signed(owner),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native,
+			100u128,
+			200u128,
+			5u64
+		));
+		assert_eq!(Assets::balance(asset_id, owner), 1_900u128);
+
+		//the pallet account cannot send the asset while it is frozen
+		assert_ok!(Assets::freeze(
+			RuntimeOrigin::signed(Dex::account_id()),
+			asset_id.into(),
+			Dex::account_id()
+		));
+		Dex::on_initialize(5);
+		System::assert_last_event(RuntimeEvent::Dex(Event::LimitOrderExpiryFailed {
+			order_id: 0,
+			owner,
+			error: sp_runtime::TokenError::Frozen.into(),
+		}));
+		assert!(Dex::limit_orders(0).is_some());
+		assert_eq!(Dex::order_book((AssetKind::Asset(asset_id), AssetKind::Native)).len(), 1);
+		assert_eq!(Assets::balance(asset_id, owner), 1_900u128);
+
+		//the order is not lost, its owner cancels it once the asset can move again
+		assert_ok!(Assets::thaw(
+			RuntimeOrigin::signed(Dex::account_id()),
+			asset_id.into(),
+			Dex::account_id()
+		));
+		assert_ok!(Dex::cancel_limit_order(RuntimeOrigin::signed(owner), 0));
+		assert_eq!(Dex::limit_orders(0), None);
+		assert_eq!(Assets::balance(asset_id, owner), 2_000u128);
+	})
+}
+
+#[test]
+fn dca_schedules_swap_every_period_until_they_end() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&1u64, 3_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&2u64, 126u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&3u64, 65u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&4u64, 35u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&5u64, 65u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &1u64, 3_000u128).is_ok());
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(1u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		//buy assets with 50 currency every 10 blocks, 120 currency in total
+		let schedule = |account_id: u64, asset_out: AssetKind<u32>, period: u64, slippage: u32| {
+			Dex::schedule_dca(
+				RuntimeOrigin::signed(account_id),
+				AssetKind::Native,
+				asset_out,
+				50u128,
+				120u128,
+				period,
+				Permill::from_percent(slippage),
+			)
+		};
+		assert_noop!(schedule(2, AssetKind::Asset(asset_id), 0, 10), Error::<Test>::InvalidPeriod);
+		assert_noop!(schedule(2, AssetKind::Native, 10, 10), Error::<Test>::InvalidPath);
+		assert_noop!(schedule(2, AssetKind::Asset(2u32), 10, 10), Error::<Test>::PoolNotFound);
+		assert_ok!(schedule(2, AssetKind::Asset(asset_id), 10, 10));
+		assert_eq!(Balances::reserved_balance(2u64), 5u128);
+		//the fee alone is above a zero slippage
+		assert_ok!(schedule(3, AssetKind::Asset(asset_id), 10, 0));
+		//the owner cannot pay a period
+		assert_ok!(schedule(4, AssetKind::Asset(asset_id), 10, 10));
+		//skips every period until it ends
+		assert_ok!(schedule(5, AssetKind::Asset(asset_id), 10, 0));
+		assert_eq!(Dex::dca_queue(2).len(), 4);
+
+		let amount_out = Dex::get_input_convert(50u128, 1_000u128, 1_000u128, FEE).unwrap();
+		Dex::on_initialize(2);
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaExecuted {
+			dca_id: 0,
+			owner: 2,
+			amount_in: 50u128,
+			amount_out,
+		}));
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaSkipped {
+			dca_id: 1,
+			owner: 3,
+			error: Error::<Test>::SlippageExceeded.into(),
+		}));
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaTerminated {
+			dca_id: 2,
+			owner: 4,
+			reason: DcaEnd::InsufficientBalance,
+		}));
+		assert_eq!(Assets::balance(asset_id, 2u64), amount_out);
+		assert_eq!(Dex::dca_schedules(2u64, 0).unwrap().budget, 70u128);
+		assert_eq!(Dex::dca_schedules(3u64, 1).unwrap().budget, 120u128);
+		assert_eq!(Dex::dca_schedules(3u64, 1).unwrap().skipped, 1);
+		assert_eq!(Dex::dca_schedules(4u64, 2), None);
+		assert_eq!(Balances::reserved_balance(4u64), 0u128);
+		assert_eq!(Dex::dca_queue(12).len(), 3);
+
+		assert_noop!(Dex::cancel_dca(RuntimeOrigin::signed(2u64), 1), Error::<Test>::DcaNotFound);
+		assert_ok!(Dex::cancel_dca(RuntimeOrigin::signed(3u64), 1));
+		System::assert_last_event(RuntimeEvent::Dex(Event::DcaTerminated {
+			dca_id: 1,
+			owner: 3,
+			reason: DcaEnd::Cancelled,
+		}));
+		assert_eq!(Balances::reserved_balance(3u64), 0u128);
+		assert_eq!(Dex::dca_queue(12).len(), 2);
+
+		//a schedule skipped MaxDcaSkips periods in a row ends
+		Dex::on_initialize(12);
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaTerminated {
+			dca_id: 3,
+			owner: 5,
+			reason: DcaEnd::TooManySkips,
+		}));
+		assert_eq!(Dex::dca_schedules(5u64, 3), None);
+		assert_eq!(Balances::reserved_balance(5u64), 0u128);
+		assert_eq!(Balances::free_balance(5u64), 65u128);
+
+		//the last period spends what is left of the budget
+		Dex::on_initialize(22);
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaTerminated {
+			dca_id: 0,
+			owner: 2,
+			reason: DcaEnd::BudgetSpent,
+		}));
+		assert_eq!(Balances::free_balance(2u64), 6u128);
+		assert_eq!(Balances::reserved_balance(2u64), 0u128);
+		assert_eq!(Dex::dca_schedules(2u64, 0), None);
+		assert_eq!(Dex::dca_queue(32).len(), 0);
+	})
+}
+
+#[test]
+fn dca_schedule_ends_after_max_dca_skips_periods_skipped_in_a_row() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let owner = 2u64;
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&1u64, 3_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&owner, 200u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &1u64, 3_000u128).is_ok());
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(1u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+		assert_ok!(Dex::schedule_dca(
+			RuntimeOrigin::signed(owner),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			10u128,
+			100u128,
+			10u64,
+			Permill::from_percent(10)
+		));
+		let pause = |paused: bool| {
+			if paused {
+				Dex::pause_pool(RuntimeOrigin::root(), pool_id.0.clone(), pool_id.1.clone())
+			} else {
+				Dex::unpause_pool(RuntimeOrigin::root(), pool_id.0.clone(), pool_id.1.clone())
+			}
+		};
+
+		//a skipped period is not charged to the budget
+		assert_ok!(pause(true));
+		Dex::on_initialize(2);
+		System::assert_has_event(RuntimeEvent::Dex(Event::DcaSkipped {
+			dca_id: 0,
+			owner,
+			error: Error::<Test>::PoolPaused.into(),
+		}));
+		let schedule = Dex::dca_schedules(owner, 0).unwrap();
+		assert_eq!((schedule.budget, schedule.skipped), (100u128, 1));
+
+		//a swap made resets the periods skipped
+		assert_ok!(pause(false));
+		Dex::on_initialize(12);
+		let schedule = Dex::dca_schedules(owner, 0).unwrap();
+		assert_eq!((schedule.budget, schedule.skipped), (90u128, 0));
+
+		//MaxDcaSkips periods skipped in a row end the schedule and give its deposit back
+		let max_skips = <<Test as crate::Config>::MaxDcaSkips as Get<u32>>::get() as u64;
+		assert_ok!(pause(true));
+		for n in 1..max_skips {
+			Dex::on_initialize(12 + 10 * n);
+			assert_eq!(Dex::dca_schedules(owner, 0).unwrap().skipped, n as u32);
+		}
+		assert_eq!(Balances::reserved_balance(owner), 5u128);
+		Dex::on_initialize(12 + 10 * max_skips);
+		System::assert_last_event(RuntimeEvent::Dex(Event::DcaTerminated {
+			dca_id: 0,
+			owner,
+			reason: DcaEnd::TooManySkips,
+		}));
+		assert_eq!(Dex::dca_schedules(owner, 0), None);
+		assert_eq!(Balances::reserved_balance(owner), 0u128);
+		assert_eq!(Balances::free_balance(owner), 190u128);
+	})
+}
+
+#[test]
+fn committed_swaps_execute_when_revealed_or_lose_their_bond() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let account_id = 1u64;
+		let sender = RuntimeOrigin::signed(account_id);
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&account_id, 3_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(
+			<Test as crate::Config>::Fungibles::mint_into(asset_id, &account_id, 3_000u128).is_ok()
+		);
+		assert_ok!(Dex::create_pool(
+			sender.clone(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		let params = |min_amount_out: u128| SwapParams {
+			path: vec![AssetKind::Native, AssetKind::Asset(asset_id)],
+			amount_in: 100u128,
+			min_amount_out,
+		};
+		let salt = [7u8; 32];
+		let commitment = Dex::commitment_of(&account_id, &params(1), &salt);
+
+		assert_ok!(Dex::commit_swap(sender.clone(), commitment));
+		assert_eq!(Balances::reserved_balance(account_id), 10u128);
+		assert_noop!(Dex::commit_swap(sender.clone(), commitment), Error::<Test>::CommitmentExists);
+
+		//the swap cannot be revealed in the block it was committed at, nor changed
+		assert_noop!(
+			Dex::reveal_swap(sender.clone(), params(1), salt),
+			Error::<Test>::RevealTooEarly
+		);
+		System::set_block_number(2);
+		assert_noop!(
+			Dex::reveal_swap(sender.clone(), params(2), salt),
+			Error::<Test>::CommitmentNotFound
+		);
+
+		let asset_amount = Dex::get_input_convert(100u128, 1_000u128, 1_000u128, FEE).unwrap();
+		assert_ok!(Dex::reveal_swap(sender.clone(), params(1), salt));
+		System::assert_has_event(RuntimeEvent::Dex(Event::SwapRevealed {
+			who: account_id,
+			commitment,
+		}));
+		assert_eq!(Balances::reserved_balance(account_id), 0u128);
+		assert_eq!(Assets::balance(asset_id, account_id), 2_000u128 + asset_amount);
+		assert_eq!(Dex::swap_commits(account_id, commitment), None);
+		assert_eq!(Dex::commit_expiries(7).len(), 0);
+
+		//a revealed swap that fails still gets its bond back
+		let commitment = Dex::commitment_of(&account_id, &params(1_000), &salt);
+		assert_ok!(Dex::commit_swap(sender.clone(), commitment));
+		System::set_block_number(3);
+		assert_ok!(Dex::reveal_swap(sender.clone(), params(1_000), salt));
+		System::assert_last_event(RuntimeEvent::Dex(Event::RevealedSwapFailed {
+			who: account_id,
+			commitment,
+			error: Error::<Test>::SlippageExceeded.into(),
+		}));
+		assert_eq!(Balances::reserved_balance(account_id), 0u128);
+
+		//the bond of a swap not revealed within the window is slashed
+		let commitment = Dex::commitment_of(&account_id, &params(1), &[8u8; 32]);
+		assert_ok!(Dex::commit_swap(sender, commitment));
+		let free_balance = Balances::free_balance(account_id);
+		Dex::on_initialize(8);
+		assert!(Dex::swap_commits(account_id, commitment).is_some());
+		Dex::on_initialize(9);
+		System::assert_last_event(RuntimeEvent::Dex(Event::SwapCommitSlashed {
+			who: account_id,
+			commitment,
+			amount: 10u128,
+		}));
+		assert_eq!(Dex::swap_commits(account_id, commitment), None);
+		assert_eq!(Balances::reserved_balance(account_id), 0u128);
+		assert_eq!(Balances::free_balance(account_id), free_balance);
+	})
+}
+
+#[test]
+fn committed_swaps_cannot_be_revealed_once_their_bond_is_slashed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&1u64, 3_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&2u64, 1_000u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &1u64, 3_000u128).is_ok());
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(1u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			1_000u128,
+			1_000u128,
+			None
+		));
+
+		let params = SwapParams {
+			path: vec![AssetKind::Native, AssetKind::Asset(asset_id)],
+			amount_in: 100u128,
+			min_amount_out: 1u128,
+		};
+		let salt = [7u8; 32];
+
+		//both accounts commit at block 1, their window ends at block 1 + RevealWindow
+		for account_id in [1u64, 2u64] {
+			let commitment = Dex::commitment_of(&account_id, &params, &salt);
+			assert_ok!(Dex::commit_swap(RuntimeOrigin::signed(account_id), commitment));
+		}
+		assert_eq!(Dex::commit_expiries(7).len(), 2);
+
+		//the last block of the window still accepts the reveal
+		System::set_block_number(6);
+		Dex::on_initialize(6);
+		assert_ok!(Dex::reveal_swap(RuntimeOrigin::signed(1u64), params.clone(), salt));
+		assert_eq!(Balances::reserved_balance(1u64), 0u128);
+		assert_eq!(Dex::commit_expiries(7).len(), 1);
+
+		//the bond of the other account is slashed and burnt right after the window
+		let total_issuance = Balances::total_issuance();
+		let commitment = Dex::commitment_of(&2u64, &params, &salt);
+		System::set_block_number(7);
+		Dex::on_initialize(7);
+		System::assert_last_event(RuntimeEvent::Dex(Event::SwapCommitSlashed {
+			who: 2u64,
+			commitment,
+			amount: 10u128,
+		}));
+		assert_eq!(Balances::reserved_balance(2u64), 0u128);
+		assert_eq!(Balances::free_balance(2u64), 990u128);
+		assert_eq!(Balances::total_issuance(), total_issuance - 10u128);
+		assert_eq!(Dex::commit_expiries(7).len(), 0);
+
+		//a reveal after the window finds no commitment and swaps nothing
+		assert_noop!(
+			Dex::reveal_swap(RuntimeOrigin::signed(2u64), params, salt),
+			Error::<Test>::CommitmentNotFound
+		);
+		assert_eq!(Assets::balance(asset_id, 2u64), 0u128);
+	})
+}
+
+#[test]
+fn batch_swaps_clear_at_a_single_price_in_on_finalize() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let asset_id = 1u32;
+		let pool_id = (AssetKind::Native, AssetKind::Asset(asset_id));
+		let recipient = DustRecipientAccount::get();
+
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&1u64, 20_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&2u64, 2_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&3u64, 10u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&4u64, 2_000u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 1u128);
+		let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 1u128);
+		assert_ok!(Dex::create_asset_helper(asset_id));
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &1u64, 20_000u128).is_ok());
+		assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &3u64, 1_000u128).is_ok());
+		assert_ok!(Dex::create_pool(
+			RuntimeOrigin::signed(1u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			Some(FEE),
+			10_000u128,
+			10_000u128,
+			None
+		));
+
+		//only the admin can switch a pool to batch mode
+		assert_noop!(
+			Dex::set_batch_mode(
+				RuntimeOrigin::signed(1u64),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				true
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Dex::submit_batch_swap(
+				RuntimeOrigin::signed(2u64),
+				AssetKind::Native,
+				AssetKind::Asset(asset_id),
+				1_000u128,
+				1u128
+			),
+			Error::<Test>::PoolNotInBatchMode
+		);
+		assert_ok!(Dex::set_batch_mode(
+			RuntimeOrigin::root(),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native,
+			true
+		));
+		System::assert_last_event(RuntimeEvent::Dex(Event::BatchModeUpdated {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			enabled: true,
+		}));
+
+		//direct swaps cannot be ordered against the batch
+		assert_noop!(
+			Dex::currency_to_asset(RuntimeOrigin::signed(2u64), 100u128, asset_id, 1u128, None),
+			Error::<Test>::PoolInBatchMode
+		);
+
+		assert_ok!(Dex::submit_batch_swap(
+			RuntimeOrigin::signed(2u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			1_000u128,
+			1u128
+		));
+		assert_ok!(Dex::submit_batch_swap(
+			RuntimeOrigin::signed(3u64),
+			AssetKind::Asset(asset_id),
+			AssetKind::Native,
+			500u128,
+			1u128
+		));
+		//this swap cannot get its minimum, it is refunded and the others clear without it
+		assert_ok!(Dex::submit_batch_swap(
+			RuntimeOrigin::signed(4u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			1_000u128,
+			10_000u128
+		));
+		assert_eq!(Dex::batch_swaps(&pool_id).len(), 3);
+		assert_eq!(Balances::free_balance(2u64), 1_000u128);
+		assert_eq!(Assets::balance(asset_id, 3u64), 500u128);
+
+		Dex::on_finalize(1);
+
+		let amount_a = 1_000u128 - FEE.mul_floor(1_000u128);
+		let amount_b = 500u128 - FEE.mul_floor(500u128);
+		let (depth_a, depth_b) = (10_000u128 + amount_a, 10_000u128 + amount_b);
+		let amount_b_out = amount_a * depth_b / depth_a;
+		let amount_a_out = amount_b * depth_a / depth_b;
+
+		System::assert_has_event(RuntimeEvent::Dex(Event::BatchSwapRefunded {
+			who: 4u64,
+			asset_in: AssetKind::Native,
+			amount_in: 1_000u128,
+		}));
+		System::assert_last_event(RuntimeEvent::Dex(Event::BatchCleared {
+			asset_a: AssetKind::Native,
+			asset_b: AssetKind::Asset(asset_id),
+			price_a: FixedU128::checked_from_rational(depth_b, depth_a).unwrap(),
+			amount_a_in: 1_000u128,
+			amount_b_in: 500u128,
+			amount_a_out,
+			amount_b_out,
+		}));
+		assert_eq!(Assets::balance(asset_id, 2u64), amount_b_out);
+		assert_eq!(Balances::free_balance(3u64), 10u128 + amount_a_out);
+		assert_eq!(Balances::free_balance(4u64), 2_000u128);
+
+		let pool = Dex::pools(&pool_id).unwrap();
+		assert_eq!(pool.reserve_a, 10_000u128 + 1_000u128 - amount_a_out);
+		assert_eq!(pool.reserve_b, 10_000u128 + 500u128 - amount_b_out);
+		assert!(pool.reserve_a * pool.reserve_b >= 10_000u128 * 10_000u128);
+		assert_eq!(Dex::batch_swaps(&pool_id).len(), 0);
+
+		//destroying the pool gives back the swaps waiting for its batch
+		assert_ok!(Dex::submit_batch_swap(
+			RuntimeOrigin::signed(4u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			1_000u128,
+			1u128
+		));
+		let liquidity = Assets::balance(pool.liquidity_asset_id, 1u64);
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1u64),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id),
+			liquidity,
+			0u128,
+			0u128,
+			None
+		));
+		assert_ok!(Dex::destroy_pool(
+			RuntimeOrigin::root(),
+			AssetKind::Native,
+			AssetKind::Asset(asset_id)
+		));
+		System::assert_has_event(RuntimeEvent::Dex(Event::BatchSwapRefunded {
+			who: 4u64,
+			asset_in: AssetKind::Native,
+			amount_in: 1_000u128,
+		}));
+		assert_eq!(Balances::free_balance(4u64), 2_000u128);
+		assert!(!Dex::batch_mode(&pool_id));
+		assert_eq!(Dex::batch_swaps(&pool_id).len(), 0);
+	})
+}
+
+//creates a pool of 10_000 currency and 10_000 of asset 1 in batch mode, account 2 holds 2_000
+//currency and account 3 holds 1_000 of the asset
+fn batch_pool() -> (AssetKind<u32>, AssetKind<u32>) {
+	let asset_id = 1u32;
+	let _ = <Test as crate::Config>::Currency::deposit_creating(&1u64, 20_000u128);
+	let _ = <Test as crate::Config>::Currency::deposit_creating(&2u64, 2_000u128);
+	let _ = <Test as crate::Config>::Currency::deposit_creating(&3u64, 10u128);
+	let _ = <Test as crate::Config>::Currency::deposit_creating(&Dex::account_id(), 1u128);
+	let recipient = DustRecipientAccount::get();
+	let _ = <Test as crate::Config>::Currency::deposit_creating(&recipient, 1u128);
+	assert_ok!(Dex::create_asset_helper(asset_id));
+	assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &1u64, 20_000u128).is_ok());
+	assert!(<Test as crate::Config>::Fungibles::mint_into(asset_id, &3u64, 1_000u128).is_ok());
+	assert_ok!(Dex::create_pool(
+		RuntimeOrigin::signed(1u64),
+		AssetKind::Native,
+		AssetKind::Asset(asset_id),
+		Some(FEE),
+		10_000u128,
+		10_000u128,
+		None
+	));
+	assert_ok!(Dex::set_batch_mode(
+		RuntimeOrigin::root(),
+		AssetKind::Native,
+		AssetKind::Asset(asset_id),
+		true
+	));
+	(AssetKind::Native, AssetKind::Asset(asset_id))
+}
+
+#[test]
+fn batch_swaps_on_one_side_clear_against_the_reserves() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let pool_id = batch_pool();
+		let (native, asset) = pool_id.clone();
+
+		for (who, amount_in) in [(2u64, 1_000u128), (2u64, 500u128)] {
+			assert_ok!(Dex::submit_batch_swap(
+				RuntimeOrigin::signed(who),
+				native.clone(),
+				asset.clone(),
+				amount_in,
+				1u128
+			));
+		}
+
+		Dex::on_finalize(1);
+
+		//both swaps get the same price, set by the whole input of the batch
+		let after_fee = |amount: u128| amount - FEE.mul_floor(amount);
+		let depth_a = 10_000u128 + after_fee(1_000u128) + after_fee(500u128);
+		let amount_out = |amount_in: u128| after_fee(amount_in) * 10_000u128 / depth_a;
+		let amount_b_out = amount_out(1_000u128) + amount_out(500u128);
+		System::assert_last_event(RuntimeEvent::Dex(Event::BatchCleared {
+			asset_a: native.clone(),
+			asset_b: asset.clone(),
+			price_a: FixedU128::checked_from_rational(10_000u128, depth_a).unwrap(),
+			amount_a_in: 1_500u128,
+			amount_b_in: 0u128,
+			amount_a_out: 0u128,
+			amount_b_out,
+		}));
+		assert_eq!(Assets::balance(1u32, 2u64), amount_b_out);
+		assert_eq!(Balances::free_balance(2u64), 500u128);
+
+		let pool = Dex::pools(&pool_id).unwrap();
+		assert_eq!(pool.reserve_a, 11_500u128);
+		assert_eq!(pool.reserve_b, 10_000u128 - amount_b_out);
+		assert!(pool.reserve_a * pool.reserve_b >= 10_000u128 * 10_000u128);
+	})
+}
+
+#[test]
+fn batch_swaps_are_refunded_when_they_cannot_clear() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let pool_id = batch_pool();
+		let (native, asset) = pool_id.clone();
+		let submit = |who: u64, asset_in: &AssetKind<u32>, asset_out: &AssetKind<u32>| {
+			Dex::submit_batch_swap(
+				RuntimeOrigin::signed(who),
+				asset_in.clone(),
+				asset_out.clone(),
+				300u128,
+				1u128,
+			)
+		};
+
+		//each swap is within the price impact allowed, but not the batch as a whole
+		assert_ok!(Dex::set_parameters(
+			RuntimeOrigin::root(),
+			Parameters { max_price_impact: Permill::from_percent(5), ..Dex::parameters() }
+		));
+		assert_ok!(submit(2u64, &native, &asset));
+		assert_ok!(submit(2u64, &native, &asset));
+		Dex::on_finalize(1);
+		System::assert_last_event(RuntimeEvent::Dex(Event::BatchSwapRefunded {
+			who: 2u64,
+			asset_in: native.clone(),
+			amount_in: 300u128,
+		}));
+		assert_eq!(Balances::free_balance(2u64), 2_000u128);
+		let pool = Dex::pools(&pool_id).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (10_000u128, 10_000u128));
+
+		//a refund that fails is reported and the input stays in the pallet account
+		assert_ok!(submit(3u64, &asset, &native));
+		assert_ok!(Dex::pause_pool(RuntimeOrigin::root(), native.clone(), asset.clone()));
+		assert_ok!(Assets::freeze(
+			RuntimeOrigin::signed(Dex::account_id()),
+			1u32.into(),
+			Dex::account_id()
+		));
+		Dex::on_finalize(2);
+		System::assert_last_event(RuntimeEvent::Dex(Event::BatchSwapRefundFailed {
+			who: 3u64,
+			asset_in: asset.clone(),
+			amount_in: 300u128,
+			error: sp_runtime::TokenError::Frozen.into(),
+		}));
+		assert_eq!(Assets::balance(1u32, 3u64), 700u128);
+		assert_ok!(Assets::thaw(
+			RuntimeOrigin::signed(Dex::account_id()),
+			1u32.into(),
+			Dex::account_id()
+		));
+		assert_ok!(Dex::unpause_pool(RuntimeOrigin::root(), native.clone(), asset.clone()));
+
+		//destroying the pool refunds both sides of its batch at once
+		assert_ok!(submit(2u64, &native, &asset));
+		assert_ok!(submit(3u64, &asset, &native));
+		let liquidity = Assets::balance(pool.liquidity_asset_id, 1u64);
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1u64),
+			native.clone(),
+			asset.clone(),
+			liquidity,
+			0u128,
+			0u128,
+			None
+		));
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::root(), native.clone(), asset.clone()));
+		System::assert_has_event(RuntimeEvent::Dex(Event::BatchSwapRefunded {
+			who: 2u64,
+			asset_in: native,
+			amount_in: 300u128,
+		}));
+		System::assert_has_event(RuntimeEvent::Dex(Event::BatchSwapRefunded {
+			who: 3u64,
+			asset_in: asset,
+			amount_in: 300u128,
+		}));
+		assert_eq!(Balances::free_balance(2u64), 2_000u128);
+		assert_eq!(Assets::balance(1u32, 3u64), 700u128);
+		assert_eq!(Dex::batch_swaps(&pool_id).len(), 0);
+
+		//nothing is left to clear at the end of the block
+		let events = System::events().len();
+		Dex::on_finalize(3);
+		assert_eq!(System::events().len(), events);
 	})
 }
@@ -0,0 +1,102 @@
+//! Weights of the dispatchables and hooks of the dex pallet whose cost grows with their input.
+//!
+//! The pallet has no benchmarks yet, so these weights only count the database accesses of each
+//! item, taken from the code they price, and leave the execution time aside.
+
+use frame_support::{traits::Get, weights::Weight};
+
+/// Reads of a swap through one pool: the pool twice, when quoting and when updating it, the
+/// parameters twice, the pause and batch flags, the timestamp, the observation state and
+/// history, the protocol fees collected and the two balances of the protocol fee transfer.
+pub const SWAP_HOP_READS: u64 = 12;
+
+/// Writes of a swap through one pool: the pool, the observation state and history, the
+/// protocol fees collected and the two balances of the protocol fee transfer.
+pub const SWAP_HOP_WRITES: u64 = 6;
+
+/// Weight of a swap along a path of `path_len` assets: the transfers of the input and the
+/// output, the parameters read to check the path, and every pool of the path.
+pub fn swap_along_path<T: frame_system::Config>(path_len: u32) -> Weight {
+	let hops = path_len.saturating_sub(1) as u64;
+	T::DbWeight::get().reads_writes(5 + SWAP_HOP_READS * hops, 4 + SWAP_HOP_WRITES * hops)
+}
+
+/// Reads and writes of giving back one open limit order: the order, its place in the order book
+/// and in the owner's orders, and the two balances of the escrow transfer.
+pub const LIMIT_ORDER_CANCEL_READS_WRITES: u64 = 5;
+
+/// Reads and writes of refunding one batch swap: the two balances of the transfer.
+pub const BATCH_SWAP_REFUND_READS_WRITES: u64 = 2;
+
+/// Reads and writes of destroying one account or approval of a liquidity asset in the assets
+/// pallet: the account or approval, and the asset details.
+pub const LIQUIDITY_ITEM_DESTROY_READS_WRITES: u64 = 2;
+
+/// Weight of destroying the liquidity asset of a removed pool, at most `items_limit` accounts
+/// and `items_limit` approvals, then refunding its metadata deposit and sweeping the two
+/// reserves of the pool.
+pub fn finish_pool_destruction<T: frame_system::Config>(items_limit: u32) -> Weight {
+	let items = LIQUIDITY_ITEM_DESTROY_READS_WRITES * 2 * items_limit as u64;
+	T::DbWeight::get().reads_writes(8 + items, 9 + items)
+}
+
+/// Weight of removing a pool with `orders` open limit orders and `batch_swaps` queued batch
+/// swaps, destroying at most `items_limit` accounts and approvals of its liquidity asset.
+pub fn destroy_pool<T: frame_system::Config>(
+	orders: u32,
+	batch_swaps: u32,
+	items_limit: u32,
+) -> Weight {
+	let items = LIMIT_ORDER_CANCEL_READS_WRITES * orders as u64 +
+		BATCH_SWAP_REFUND_READS_WRITES * batch_swaps as u64;
+	T::DbWeight::get()
+		.reads_writes(6 + items, 10 + items)
+		.saturating_add(finish_pool_destruction::<T>(items_limit))
+}
+
+/// Reads of filling one limit order: the order, and its quote and swap through the pool, with
+/// the pool, the parameters and the two balances of the escrow release, besides the swap itself.
+pub const LIMIT_ORDER_FILL_READS: u64 = 5 + SWAP_HOP_READS;
+
+/// Writes of filling one limit order: the two balances of the keeper fee and of the escrow
+/// release, the input and output transfers of the swap, the swap itself, and the order, its
+/// place in the order book and its expiry block.
+pub const LIMIT_ORDER_FILL_WRITES: u64 = 11 + SWAP_HOP_WRITES;
+
+/// Weight of filling `orders` limit orders submitted by a keeper, checked against the keepers.
+pub fn execute_limit_orders<T: frame_system::Config>(orders: u32) -> Weight {
+	let orders = orders as u64;
+	T::DbWeight::get()
+		.reads_writes(1 + LIMIT_ORDER_FILL_READS * orders, LIMIT_ORDER_FILL_WRITES * orders)
+}
+
+/// Reads of clearing the batch of one pool: the batch swaps, the pool, the pause flag and the
+/// flash swap lock, the parameters for the two price impacts and the two protocol fees, the
+/// timestamp, the observation state and history, and the protocol fees collected and the two
+/// balances of each protocol fee transfer.
+pub const BATCH_POOL_READS: u64 = 17;
+
+/// Writes of clearing the batch of one pool: the batch swaps, the pool, the observation state
+/// and history, and the protocol fees collected and the two balances of each protocol fee
+/// transfer.
+pub const BATCH_POOL_WRITES: u64 = 10;
+
+/// Reads and writes of one swap of a batch: the two balances of the transfer of its output, or
+/// of its input when it is refunded.
+pub const BATCH_SWAP_SETTLE_READS_WRITES: u64 = 2;
+
+/// Weight of clearing the batches of `pools` pools in `on_finalize`, each with at most
+/// `max_batch_swaps` swaps.
+pub fn clear_batches<T: frame_system::Config>(pools: u32, max_batch_swaps: u32) -> Weight {
+	let pools = pools as u64;
+	let swaps = BATCH_SWAP_SETTLE_READS_WRITES * pools * max_batch_swaps as u64;
+	T::DbWeight::get()
+		.reads_writes(BATCH_POOL_READS * pools + swaps, BATCH_POOL_WRITES * pools + swaps)
+}
+
+/// Weight of submitting a swap to a batch: the pool, the pause flag, the flash swap lock, the
+/// batch mode and the batch swaps of the pool, and the two balances of the input transfer. Its
+/// clearing is already counted in `on_initialize`.
+pub fn submit_batch_swap<T: frame_system::Config>() -> Weight {
+	T::DbWeight::get().reads_writes(7, 3)
+}
@@ -0,0 +1,68 @@
+//! Types and runtime API of the dex, shared by the pallet, the runtime and the node.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_arithmetic::{FixedU128, Permill};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// A pool as seen from outside the runtime.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct PoolInfo<AssetKind, AssetId, Balance> {
+	/// One side of the pool, the lower of the pair.
+	pub asset_a: AssetKind,
+	/// The other side of the pool.
+	pub asset_b: AssetKind,
+	pub reserve_a: Balance,
+	pub reserve_b: Balance,
+	/// Fungible asset minted to the liquidity providers of the pool.
+	pub liquidity_asset_id: AssetId,
+	/// Total issuance of the liquidity asset, including the liquidity locked at creation.
+	pub liquidity_supply: Balance,
+	/// Fee charged on the input of every swap.
+	pub fee: Permill,
+	/// Whether swaps and new liquidity are stopped, by the pool or by the whole dex.
+	pub paused: bool,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Read-only access to the pools of the dex, so that front-ends don't need to decode its
+	/// storage or to reimplement its math.
+	pub trait DexApi<AssetKind, AssetId, Balance>
+	where
+		AssetKind: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// The pool of a pair, in any order.
+		fn get_pool(
+			asset_a: AssetKind,
+			asset_b: AssetKind,
+		) -> Option<PoolInfo<AssetKind, AssetId, Balance>>;
+
+		/// Every pool of the dex.
+		fn list_pools() -> Vec<PoolInfo<AssetKind, AssetId, Balance>>;
+
+		/// Amount received by swapping `amount_in` along `path`, fees included.
+		fn quote_exact_in(path: Vec<AssetKind>, amount_in: Balance) -> Option<Balance>;
+
+		/// Amount to swap along `path` to receive `amount_out`, fees included.
+		fn quote_exact_out(path: Vec<AssetKind>, amount_out: Balance) -> Option<Balance>;
+
+		/// Amounts of `asset_a` and `asset_b` that burning `liquidity` of their pool gives back.
+		fn lp_share_value(
+			asset_a: AssetKind,
+			asset_b: AssetKind,
+			liquidity: Balance,
+		) -> Option<(Balance, Balance)>;
+
+		/// Price of a fungible asset in currency and of the currency in the asset, fees excluded.
+		fn spot_price(asset_id: AssetId) -> Option<(FixedU128, FixedU128)>;
+	}
+}
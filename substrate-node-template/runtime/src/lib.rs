@@ -16,8 +16,8 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, Convert, IdentifyAccount, NumberFor, One,
-		Verify,
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, Convert,
+		IdentifyAccount, NumberFor, One, Verify,
 	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
@@ -33,8 +33,8 @@ use frame_system::{EnsureRoot, EnsureSigned};
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		AsEnsureOriginWithArg, ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem,
-		Randomness, StorageInfo,
+		AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU32, ConstU64, ConstU8,
+		KeyOwnerProofSystem, Randomness, StorageInfo,
 	},
 	weights::{
 		constants::{
@@ -50,7 +50,7 @@ pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, CurrencyAdapter, Multiplier};
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{FixedU128, Perbill, Permill};
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -71,6 +71,12 @@ pub type Index = u32;
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
+/// Id of a fungible asset.
+pub type AssetId = u32;
+
+/// One side of a dex pool, either the currency or a fungible asset.
+pub type AssetKind = pallet_dex::AssetKind<AssetId>;
+
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
 /// of data like extrinsics, allowing for them to continue syncing the network through upgrades
@@ -107,10 +113,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -284,15 +290,30 @@ parameter_types! {
 	pub const MetadataDepositBase: Balance = 10;
 	pub const MetadataDepositPerByte: Balance = 1;
 	pub const PalletIdentification: PalletId = PalletId(*b"palle/*t");
-	pub const FeeConst: Balance = 3;
-	pub const ThousandConst: Balance = 1000;
+	pub ProtocolFeeRecipientAccount: AccountId =
+		PalletId(*b"dex/fees").into_account_truncating();
+	pub DustRecipientAccount: AccountId = PalletId(*b"dex/dust").into_account_truncating();
+	pub const KeeperFee: Permill = Permill::from_parts(1_000);
+}
+
+impl frame_system::offchain::SigningTypes for Runtime {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	RuntimeCall: From<C>,
+{
+	type Extrinsic = UncheckedExtrinsic;
+	type OverarchingCall = RuntimeCall;
 }
 
 impl pallet_assets::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Balance = u128;
-	type AssetId = u32;
-	type AssetIdParameter = codec::Compact<u32>;
+	type AssetId = AssetId;
+	type AssetIdParameter = codec::Compact<AssetId>;
 	type Currency = Balances;
 	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
 	type ForceOrigin = EnsureRoot<AccountId>;
@@ -339,11 +360,38 @@ impl pallet_dex::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
 	type Fungibles = Assets;
-	type AssetId = u32;
+	type AssetId = AssetId;
 	type AssetBalance = u128;
 	type PalletId = PalletIdentification;
-	type Fee = FeeConst;
-	type Thousand = ThousandConst;
+	type MaxHops = ConstU32<4>;
+	type MaxFeeTiers = ConstU32<8>;
+	type ProtocolFeeRecipient = ProtocolFeeRecipientAccount;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type MinimumLiquidity = ConstU128<1_000>;
+	type FirstLiquidityAssetId = ConstU32<1_000_000>;
+	type NativeDecimals = ConstU8<12>;
+	type StringLimit = StringLimit;
+	type DustRecipient = DustRecipientAccount;
+	type DestroyGracePeriod = ConstU32<{ 7 * DAYS }>;
+	type DestroyItemsLimit = ConstU32<1_000>;
+	type PoolCreateOrigin = EnsureSigned<AccountId>;
+	type PoolCreationDeposit = ConstU128<{ 20 * EXISTENTIAL_DEPOSIT }>;
+	type RestrictListings = ConstBool<false>;
+	type PauseOrigin = EnsureRoot<AccountId>;
+	type UnixTime = Timestamp;
+	type MaxObservations = ConstU32<64>;
+	type FlashSwapHandler = ();
+	type MaxOrdersPerBook = ConstU32<256>;
+	type KeeperAuthorityId = pallet_dex::crypto::KeeperId;
+	type KeeperFee = KeeperFee;
+	type KeeperPriority = ConstU64<{ u64::MAX / 2 }>;
+	type MaxDcaPerBlock = ConstU32<64>;
+	type DcaDeposit = ConstU128<{ 10 * EXISTENTIAL_DEPOSIT }>;
+	type MaxDcaSkips = ConstU32<3>;
+	type CommitBond = ConstU128<{ 10 * EXISTENTIAL_DEPOSIT }>;
+	type RevealWindow = ConstU32<10>;
+	type MaxCommitsPerBlock = ConstU32<256>;
+	type MaxBatchSwaps = ConstU32<128>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -385,6 +433,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_dex::CheckDeadline<Runtime>,
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -399,8 +448,12 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
+/// Storage migrations applied on runtime upgrade.
+pub type Migrations = (pallet_dex::migrations::v1::MigrateToV1<Runtime>,);
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -567,6 +620,53 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl dex_primitives::DexApi<Block, AssetKind, AssetId, Balance> for Runtime {
+		fn get_pool(
+			asset_a: AssetKind,
+			asset_b: AssetKind,
+		) -> Option<pallet_dex::PoolInfoOf<Runtime>> {
+			Dex::get_pool(asset_a, asset_b).ok().map(Dex::pool_info)
+		}
+
+		fn list_pools() -> Vec<pallet_dex::PoolInfoOf<Runtime>> {
+			Dex::list_pools()
+		}
+
+		fn quote_exact_in(
+			path: Vec<AssetKind>,
+			amount_in: Balance,
+		) -> Option<Balance> {
+			Dex::quote(amount_in, &path).ok()
+		}
+
+		fn quote_exact_out(
+			path: Vec<AssetKind>,
+			amount_out: Balance,
+		) -> Option<Balance> {
+			Dex::quote_exact_out(amount_out, &path).ok()
+		}
+
+		fn lp_share_value(
+			asset_a: AssetKind,
+			asset_b: AssetKind,
+			liquidity: Balance,
+		) -> Option<(Balance, Balance)> {
+			let pool = Dex::get_pool(asset_a.clone(), asset_b).ok()?;
+			let (amount_a, amount_b) = Dex::liquidity_value(&pool, liquidity).ok()?;
+
+			// the amounts are returned in the order of the requested pair
+			if asset_a == pool.asset_a {
+				Some((amount_a, amount_b))
+			} else {
+				Some((amount_b, amount_a))
+			}
+		}
+
+		fn spot_price(asset_id: AssetId) -> Option<(FixedU128, FixedU128)> {
+			Dex::spot_price(asset_id).ok()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -633,7 +733,12 @@ impl_runtime_apis! {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use frame_support::traits::WhitelistedStorageKeys;
+	use dex_primitives::runtime_decl_for_DexApi::DexApi;
+	use frame_support::{
+		assert_ok,
+		sp_io::TestExternalities,
+		traits::{fungibles::Mutate, Currency, WhitelistedStorageKeys},
+	};
 	use sp_core::hexdisplay::HexDisplay;
 	use std::collections::HashSet;
 
@@ -665,4 +770,73 @@ mod tests {
 			whitelist.contains("26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7")
 		);
 	}
+
+	#[test]
+	fn dex_api_quotes_match_the_pallet_amounts() {
+		let storage = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		TestExternalities::new(storage).execute_with(|| {
+			let creator = AccountId::new([1u8; 32]);
+			let (first, second) = (1u32, 2u32);
+			let _ = Balances::deposit_creating(&creator, 1_000_000_000);
+			let _ = Balances::deposit_creating(&Dex::account_id(), 1_000_000_000);
+
+			//a currency pool and an asset pool, so that quotes go through two hops
+			for asset_id in [first, second] {
+				assert_ok!(Dex::create_asset_helper(asset_id));
+				assert_ok!(Assets::mint_into(asset_id, &creator, 1_000_000_000));
+			}
+			assert_ok!(Dex::create_pool(
+				RuntimeOrigin::signed(creator.clone()),
+				AssetKind::Native,
+				AssetKind::Asset(first),
+				None,
+				10_000_000,
+				20_000_000,
+				None
+			));
+			assert_ok!(Dex::create_pool(
+				RuntimeOrigin::signed(creator),
+				AssetKind::Asset(first),
+				AssetKind::Asset(second),
+				None,
+				30_000_000,
+				15_000_000,
+				None
+			));
+
+			let path = vec![AssetKind::Native, AssetKind::Asset(first), AssetKind::Asset(second)];
+			let amounts_out = Dex::get_amounts_out(&path, 100_000).unwrap();
+			assert_eq!(
+				<Runtime as DexApi<Block, AssetKind, AssetId, Balance>>::quote_exact_in(
+					path.clone(),
+					100_000
+				),
+				amounts_out.last().cloned()
+			);
+			let amounts_in = Dex::get_amounts_in(&path, 100_000).unwrap();
+			assert_eq!(
+				<Runtime as DexApi<Block, AssetKind, AssetId, Balance>>::quote_exact_out(
+					path.clone(),
+					100_000
+				),
+				amounts_in.first().cloned()
+			);
+
+			//quotes the pallet cannot calculate are none instead of an error
+			assert_eq!(
+				<Runtime as DexApi<Block, AssetKind, AssetId, Balance>>::quote_exact_in(
+					vec![AssetKind::Native],
+					100_000
+				),
+				None
+			);
+			assert_eq!(
+				<Runtime as DexApi<Block, AssetKind, AssetId, Balance>>::quote_exact_out(
+					path,
+					u128::MAX
+				),
+				None
+			);
+		});
+	}
 }